@@ -7,7 +7,10 @@ use colored::Colorize;
 use lt_world::LtWorld;
 use notify::RecursiveMode;
 use notify_debouncer_mini::new_debouncer;
-use typst_languagetool::{LanguageTool, LanguageToolBackend, Suggestion};
+use typst::{syntax::Source, World};
+use typst_languagetool::{
+	profile, report, rules::Rules, Diagnostic, LanguageTool, LanguageToolBackend, Suggestion,
+};
 
 use std::{
 	collections::HashMap,
@@ -19,13 +22,21 @@ use std::{
 enum Task {
 	Check,
 	Watch,
+	/// Write a report of all findings.
+	Report,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ReportFormat {
+	Html,
+	Markdown,
 }
 
 #[derive(Parser, Debug)]
 struct Args {
 	task: Task,
 
-	/// File to check, may be a folder with `watch`.
+	/// File or folder to check, files ignored by `.gitignore` or `.ltignore` are skipped in folders.
 	#[clap(short, long, default_value = None)]
 	path: Option<PathBuf>,
 
@@ -46,6 +57,39 @@ struct Args {
 	#[clap(long, default_value_t = 1000)]
 	chunk_size: usize,
 
+	/// Flag repeated sentences and sentence openings.
+	#[clap(long, default_value_t = false)]
+	duplicates: bool,
+
+	/// Flag non-inclusive or outdated terminology.
+	#[clap(long, default_value_t = false)]
+	inclusive: bool,
+
+	/// Flag missing non-breaking spaces before units, wrong decimal separators and hyphen ranges.
+	#[clap(long, default_value_t = false)]
+	typography: bool,
+
+	/// Check strings for the document metadata and template arguments (title, author, ...).
+	#[clap(long, default_value_t = false)]
+	metadata: bool,
+
+	/// Report elements which are not checked (images, ...).
+	#[clap(long, default_value_t = false)]
+	skipped: bool,
+
+	/// Combine findings of the same whitespace or punctuation rule, which are only separated by
+	/// whitespace.
+	#[clap(long, default_value_t = false)]
+	merge_adjacent: bool,
+
+	/// Format for `report`.
+	#[clap(long, default_value = "html")]
+	format: ReportFormat,
+
+	/// Output file for `report`, defaults to stdout.
+	#[clap(short, long, default_value = None)]
+	output: Option<PathBuf>,
+
 	/// Print results without annotations for easy regex evaluation.
 	#[clap(long, default_value_t = false)]
 	plain: bool,
@@ -83,24 +127,38 @@ async fn main() -> anyhow::Result<()> {
 	match args.task {
 		Task::Check => check(args, lt, world).await?,
 		Task::Watch => watch(args, lt, world).await?,
+		Task::Report => report(args, lt, world).await?,
 	}
 
 	Ok(())
 }
 
+/// Files of the path, all files of it for a folder.
+fn files(args: &Args, world: &LtWorld) -> anyhow::Result<Vec<PathBuf>> {
+	let path = args
+		.path
+		.as_ref()
+		.or_else(|| args.main.as_ref())
+		.context("No path or main specified")?;
+	Ok(match path.is_dir() {
+		true => world.files(path),
+		false => vec![path.clone()],
+	})
+}
+
 async fn check(args: Args, mut lt: LanguageTool, mut world: LtWorld) -> anyhow::Result<()> {
-	handle_file(
-		args.path
-			.as_ref()
-			.or_else(|| args.main.as_ref())
-			.context("No path or main specified")?,
-		&mut lt,
-		&args,
-		&mut world,
-		args.chunk_size,
-		&mut Cache::new(),
-	)
-	.await?;
+	let mut cache = Cache::new();
+	for file in files(&args, &world)? {
+		handle_file(
+			&file,
+			&mut lt,
+			&args,
+			&mut world,
+			args.chunk_size,
+			&mut cache,
+		)
+		.await?;
+	}
 	Ok(())
 }
 
@@ -118,6 +176,9 @@ async fn watch(args: Args, mut lt: LanguageTool, mut world: LtWorld) -> anyhow::
 				Some(ext) if ext == "typ" => {},
 				_ => continue,
 			}
+			if world.is_ignored(&event.path) {
+				continue;
+			}
 
 			handle_file(
 				&event.path,
@@ -133,6 +194,36 @@ async fn watch(args: Args, mut lt: LanguageTool, mut world: LtWorld) -> anyhow::
 	Ok(())
 }
 
+async fn report(args: Args, mut lt: LanguageTool, world: LtWorld) -> anyhow::Result<()> {
+	let mut cache = Cache::new();
+	let mut results = Vec::new();
+	for file in files(&args, &world)? {
+		if let Some(result) =
+			file_diagnostics(&file, &mut lt, &args, &world, args.chunk_size, &mut cache).await?
+		{
+			results.push((file, result));
+		}
+	}
+	let files = results
+		.iter()
+		.map(|(path, (source, diagnostics))| report::FileReport {
+			name: path.display().to_string(),
+			source,
+			diagnostics: diagnostics.clone(),
+		})
+		.collect::<Vec<_>>();
+	let format = match args.format {
+		ReportFormat::Html => report::Format::Html,
+		ReportFormat::Markdown => report::Format::Markdown,
+	};
+	let report = report::render(&files, format);
+	match &args.output {
+		Some(output) => std::fs::write(output, report)?,
+		None => print!("{}", report),
+	}
+	Ok(())
+}
+
 async fn handle_file(
 	path: &Path,
 	lt: &mut LanguageTool,
@@ -141,27 +232,94 @@ async fn handle_file(
 	chunk_size: usize,
 	cache: &mut Cache,
 ) -> anyhow::Result<()> {
+	let Some((source, diagnostics)) =
+		file_diagnostics(path, lt, args, world, chunk_size, cache).await?
+	else {
+		return Ok(());
+	};
+
+	if args.plain {
+		println!("START");
+		for diagnostic in diagnostics {
+			output::plain(&path, &source, diagnostic);
+		}
+		println!("END");
+	} else {
+		println!("{}", "\n\nChecking Document\n".green().bold());
+		for diagnostic in diagnostics {
+			output::pretty(&path, &source, diagnostic);
+		}
+	}
+
+	Ok(())
+}
+
+/// Diagnostics of the file, `None` if the document fails to compile.
+async fn file_diagnostics(
+	path: &Path,
+	lt: &mut LanguageTool,
+	args: &Args,
+	world: &LtWorld,
+	chunk_size: usize,
+	cache: &mut Cache,
+) -> anyhow::Result<Option<(Source, Vec<Diagnostic>)>> {
 	let world = world.with_main(args.main.clone().unwrap_or(path.to_owned()));
 	let doc = match world.compile() {
 		Ok(doc) => doc,
 		Err(err) => {
+			// stderr, so reports on stdout stay valid
 			if args.plain {
-				println!("Failed to compile document!");
+				eprintln!("Failed to compile document!");
 			} else {
-				println!("{}", "Failed to compile document!\n".red().bold());
+				eprintln!("{}", "Failed to compile document!\n".red().bold());
 			}
 			for dia in err {
-				println!("\t{:?}", dia);
+				eprintln!("\t{:?}", dia);
 			}
-			return Ok(());
+			return Ok(None);
 		},
 	};
 
 	let file_id = world.file_id(path).unwrap();
-	let paragraphs = typst_languagetool::convert::document(&doc, chunk_size, file_id);
+	let (mut paragraphs, skipped) =
+		typst_languagetool::convert::document(&doc, chunk_size, file_id);
+	let source = world.source(file_id)?;
+	let built_in = profile::built_in();
+	let profiles = profile::active(&built_in, &source, &world.main());
+	let overlays = profiles.iter().any(|profile| profile.overlays);
+	if overlays {
+		typst_languagetool::convert::remove_overlays(&mut paragraphs);
+	}
+	let excluded = [
+		profile::ignored(&source, &profiles),
+		profile::notes(&source, &profiles),
+	]
+	.concat();
+	typst_languagetool::convert::remove_ranges(&source, &mut paragraphs, &excluded);
+	if args.metadata {
+		paragraphs.extend(typst_languagetool::convert::metadata(&source, &paragraphs));
+	}
+	paragraphs.extend(typst_languagetool::convert::notes(
+		&source,
+		&paragraphs,
+		&profiles,
+	));
 	let mut collector = typst_languagetool::FileCollector::new(file_id, &world);
+	if overlays {
+		collector.deduplicate();
+	}
+	if args.skipped {
+		collector.add_skipped(&skipped);
+	}
 	let mut next_cache = Cache::new();
-	for (text, mapping) in paragraphs {
+	let rules = Rules {
+		duplicates: args.duplicates,
+		inclusive: args.inclusive,
+		typography: args.typography,
+		..Default::default()
+	};
+	let local = rules.check(&source, &paragraphs);
+	for ((text, mapping), local) in paragraphs.into_iter().zip(local) {
 		let lang = mapping.long_language();
 		let suggestions = if let Some(suggestions) = cache.get(&text) {
 			suggestions
@@ -169,27 +327,17 @@ async fn handle_file(
 			lt.check_text(lang, &text).await?
 		};
 
-		collector.add(&suggestions, mapping);
+		let mut found = suggestions.clone();
+		found.extend(local);
+		if args.merge_adjacent {
+			typst_languagetool::merge_adjacent(&text, &mut found);
+		}
+		collector.add(&found, &mapping);
 		next_cache.insert(text, suggestions);
 	}
 	*cache = next_cache;
 
-	let (source, diagnostics) = collector.finish();
-
-	if args.plain {
-		println!("START");
-		for diagnostic in diagnostics {
-			output::plain(&path, &source, diagnostic);
-		}
-		println!("END");
-	} else {
-		println!("{}", "\n\nChecking Document\n".green().bold());
-		for diagnostic in diagnostics {
-			output::pretty(&path, &source, diagnostic);
-		}
-	}
-
-	Ok(())
+	Ok(Some(collector.finish()))
 }
 
 #[derive(Debug)]
@@ -2,7 +2,7 @@ use std::{io::stdout, io::Write, ops::Not, path::Path};
 
 use annotate_snippets::{Level, Renderer, Snippet};
 use typst::syntax::Source;
-use typst_languagetool::Diagnostic;
+use typst_languagetool::{Diagnostic, Severity};
 
 const MAX_SUGGESTIONS: usize = 20;
 
@@ -13,12 +13,13 @@ pub fn plain(file: &Path, source: &Source, diagnostic: Diagnostic) {
 	let (end_line, end_column) = byte_to_position(source, diagnostic.locations[0].end);
 	write!(
 		out,
-		"{} {}:{}-{}:{} info {}",
+		"{} {}:{}-{}:{} {} {}",
 		file.display(),
 		start_line + 1,
 		start_column + 1,
 		end_line + 1,
 		end_column + 1,
+		severity_name(diagnostic.severity),
 		diagnostic.message,
 	)
 	.unwrap();
@@ -61,7 +62,8 @@ pub fn pretty(file: &Path, source: &Source, diagnostic: Diagnostic) {
 	let start = diagnostic.locations[0].start - context.start;
 	let end = diagnostic.locations[0].end - context.start;
 
-	snippet = snippet.annotation(Level::Info.span(start..end).label(&diagnostic.message));
+	let level = severity_level(diagnostic.severity);
+	snippet = snippet.annotation(level.span(start..end).label(&diagnostic.message));
 
 	for replacement in diagnostic
 		.replacements
@@ -71,7 +73,7 @@ pub fn pretty(file: &Path, source: &Source, diagnostic: Diagnostic) {
 	{
 		snippet = snippet.annotation(Level::Help.span(end..end).label(&replacement));
 	}
-	let message = Level::Info
+	let message = level
 		.title(&diagnostic.rule_description)
 		.id(&diagnostic.rule_id)
 		.snippet(snippet);
@@ -80,6 +82,24 @@ pub fn pretty(file: &Path, source: &Source, diagnostic: Diagnostic) {
 	println!("{}", renderer.render(message));
 }
 
+fn severity_name(severity: Severity) -> &'static str {
+	match severity {
+		Severity::Error => "error",
+		Severity::Warning => "warning",
+		Severity::Information => "info",
+		Severity::Hint => "hint",
+	}
+}
+
+fn severity_level(severity: Severity) -> Level {
+	match severity {
+		Severity::Error => Level::Error,
+		Severity::Warning => Level::Warning,
+		Severity::Information => Level::Info,
+		Severity::Hint => Level::Note,
+	}
+}
+
 fn byte_to_position(source: &Source, index: usize) -> (usize, usize) {
 	let line = source.byte_to_line(index).unwrap();
 	let start = source.line_to_byte(line).unwrap();
@@ -1,27 +1,76 @@
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+mod quota;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Not;
+use std::path::{Component, Path, PathBuf};
 
 use anyhow::Context;
 use crossbeam_channel::RecvTimeoutError;
-use lsp_server::{Connection, ExtractError, Message, Notification, Request, RequestId, Response};
+use lsp_server::{
+	Connection, ErrorCode, ExtractError, Message, Notification, Request, RequestId, Response,
+};
 use lsp_types::notification::*;
 use lsp_types::request::*;
 use lsp_types::*;
 use lt_world::LtWorld;
+use quota::{Quota, QuotaReached, Statistics, Usage};
 use serde_json::Value;
-use typst::syntax::Source;
-use typst_languagetool::{LanguageTool, LanguageToolBackend, Suggestion};
+use typst::{syntax::Source, World};
+#[cfg(feature = "spellcheck")]
+use typst_languagetool::spell::Spellcheck;
+use typst_languagetool::{
+	profile::{self, Profile},
+	report,
+	rules::Rules,
+	scope, LanguageTool, LanguageToolBackend, Severity, Suggestion,
+};
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 #[serde(default)]
 struct InitOptions {
 	/// Additional allowed words
 	dictionary: HashMap<String, Vec<String>>,
+	/// Hunspell dictionaries (`.dic` file, with the `.aff` file next to it) for language codes
+	/// Changed paragraphs are checked with them while typing, until the LanguageTool check is done
+	/// Requires the feature `spellcheck`
+	spellcheck: HashMap<String, PathBuf>,
 	/// Languagetool rules to ignore (WHITESPACE_RULE, ...)
 	disabled_checks: HashMap<String, Vec<String>>,
 
+	/// Preferred language codes, unsupported codes are reported with the closest supported one
 	languages: Vec<String>,
 
+	/// Flag repeated sentences and sentence openings
+	duplicates: bool,
+	/// Flag non-inclusive or outdated terminology
+	inclusive_language: bool,
+	/// Additional terms with alternatives for language codes ("en", ...), empty alternatives accept the term
+	inclusive_terms: HashMap<String, HashMap<String, Vec<String>>>,
+	/// Flag missing non-breaking spaces before units, wrong decimal separators and hyphen ranges
+	typography: bool,
+	/// Phrases for language codes, findings inside them are ignored
+	allowed_phrases: HashMap<String, Vec<String>>,
+	/// Check strings for the document metadata and template arguments (title, author, ...)
+	metadata: bool,
+	/// Only check content under these labels (`<results>`) or heading paths (`Methods/Setup`)
+	/// Can be changed with the `typst-languagetool.scope` command
+	scope: Vec<String>,
+	/// Hint for elements which are not checked (images, drawings, ...), they are always logged
+	report_skipped: bool,
+	/// Combine findings of the same whitespace or punctuation rule, which are only separated by
+	/// whitespace
+	merge_adjacent: bool,
+	/// Conversion profiles for packages, merged with the built-in ones (`polylux`, `touying`)
+	/// `{ "packages": [...], "notes": [...], "ignore": [...], "overlays": true }` with the packages
+	/// enabling it when imported, speaker note functions, functions whose output is not checked and
+	/// whether pages repeating the next page are checked once
+	profiles: HashMap<String, Profile>,
+
+	/// Severity for LanguageTool categories (TYPOS, GRAMMAR, ...)
+	category_severity: HashMap<String, Severity>,
+	/// Severity for rules (UPPERCASE_SENTENCE_START, ...), overrides the category severity
+	rule_severity: HashMap<String, Severity>,
+
 	/// use bundled languagetool
 	bundled: bool,
 	/// use external JAR for languagetool
@@ -30,13 +79,30 @@ struct InitOptions {
 	host: Option<String>,
 	/// port for remote languagetool
 	port: Option<String>,
+	/// Requests and bytes per minute for remote languagetool, checks are delayed until it allows
+	/// requests again
+	/// Defaults to the free tier (20 requests, 75KB) for api.languagetool.org
+	quota: Option<Quota>,
 
 	/// Size for chunk send to LanguageTool
 	chunk_size: usize,
 	/// Duration to wait for additional changes before checking the file
 	/// Leave empty to only check on open and save
+	/// Only the changed paragraphs are checked, the complete chunks are checked on save
+	/// The document is still compiled and converted completely, only the requests to LanguageTool are limited
 	#[serde(with = "humantime_serde")]
 	on_change: Option<std::time::Duration>,
+	/// Duration without activity after which the project is checked again
+	/// All files of the project with `main`, else the open files
+	#[serde(with = "humantime_serde")]
+	recheck: Option<std::time::Duration>,
+	/// Remove the diagnostics of a file when it is closed
+	clear_on_close: bool,
+	/// Only check part of large documents, results of already checked parts are kept
+	/// `{ "first": 50 }` for the first paragraphs of a file
+	/// `{ "visible": 20 }` for chunks within these lines of the range sent with the
+	/// `typst-languagetool/visibleRange` request, the start of the file without it
+	draft_mode: Option<DraftMode>,
 
 	/// Project Root
 	root: Option<PathBuf>,
@@ -48,16 +114,35 @@ impl Default for InitOptions {
 	fn default() -> Self {
 		Self {
 			dictionary: HashMap::new(),
+			spellcheck: HashMap::new(),
 			disabled_checks: HashMap::new(),
 			languages: Vec::new(),
 
+			duplicates: false,
+			inclusive_language: false,
+			inclusive_terms: HashMap::new(),
+			typography: false,
+			allowed_phrases: HashMap::new(),
+			metadata: false,
+			scope: Vec::new(),
+			report_skipped: false,
+			merge_adjacent: false,
+			profiles: HashMap::new(),
+
+			category_severity: HashMap::new(),
+			rule_severity: HashMap::new(),
+
 			bundled: false,
 			jar_location: None,
 			host: None,
 			port: None,
+			quota: None,
 
 			chunk_size: 1000,
 			on_change: None,
+			recheck: None,
+			clear_on_close: true,
+			draft_mode: None,
 
 			root: None,
 			main: None,
@@ -65,6 +150,39 @@ impl Default for InitOptions {
 	}
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum DraftMode {
+	/// Number of paragraphs
+	First(usize),
+	/// Number of lines around the visible range
+	Visible(usize),
+}
+
+enum StatisticsRequest {}
+
+impl lsp_types::request::Request for StatisticsRequest {
+	/// `null` or `{}`.
+	type Params = Option<Value>;
+	type Result = Statistics;
+	const METHOD: &'static str = "typst-languagetool/statistics";
+}
+
+enum VisibleRange {}
+
+impl lsp_types::request::Request for VisibleRange {
+	type Params = VisibleRangeParams;
+	type Result = ();
+	const METHOD: &'static str = "typst-languagetool/visibleRange";
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct VisibleRangeParams {
+	text_document: TextDocumentIdentifier,
+	range: Range,
+}
+
 fn create_language_map(codes: Vec<String>) -> HashMap<String, String> {
 	codes
 		.into_iter()
@@ -89,6 +207,60 @@ impl InitOptions {
 		Ok(lt)
 	}
 
+	#[cfg(feature = "spellcheck")]
+	fn create_spellcheck(&self) -> Spellcheck {
+		let mut spellcheck = Spellcheck::default();
+		for (lang, path) in &self.spellcheck {
+			if let Err(err) = spellcheck.load(lang.clone(), path) {
+				eprintln!("Failed to load dictionary {}: {}", path.display(), err);
+			}
+		}
+		for (lang, words) in &self.dictionary {
+			spellcheck.allow_words(lang, words);
+		}
+		spellcheck
+	}
+
+	fn into_options(self) -> Options {
+		let hosted = self
+			.host
+			.as_ref()
+			.is_some_and(|host| host.contains("api.languagetool.org"));
+		#[cfg(not(feature = "spellcheck"))]
+		if self.spellcheck.is_empty().not() {
+			eprintln!("Feature 'spellcheck' is disabled.");
+		}
+		Options {
+			#[cfg(feature = "spellcheck")]
+			spellcheck: self.create_spellcheck(),
+			quota: self.quota.or(hosted.then_some(Quota::FREE)),
+			on_change: self.on_change,
+			recheck: self.recheck,
+			clear_on_close: self.clear_on_close,
+			draft_mode: self.draft_mode,
+			chunk_size: self.chunk_size,
+			language_codes: create_language_map(self.languages),
+			main: self.main,
+			metadata: self.metadata,
+			scope: self.scope,
+			report_skipped: self.report_skipped,
+			merge_adjacent: self.merge_adjacent,
+			profiles: profile::built_in()
+				.into_iter()
+				.chain(self.profiles)
+				.collect(),
+			category_severity: self.category_severity,
+			rule_severity: self.rule_severity,
+			rules: Rules {
+				duplicates: self.duplicates,
+				inclusive: self.inclusive_language,
+				inclusive_terms: self.inclusive_terms,
+				typography: self.typography,
+				allowed_phrases: self.allowed_phrases,
+			},
+		}
+	}
+
 	fn make_absolute(&mut self) {
 		fn make_absolute(cwd: &Path, path: &mut Option<PathBuf>) {
 			if let Some(path) = path {
@@ -104,6 +276,12 @@ impl InitOptions {
 	}
 }
 
+const SCOPE_COMMAND: &str = "typst-languagetool.scope";
+const FIXES_COMMAND: &str = "typst-languagetool.preferredFixes";
+const REPORT_COMMAND: &str = "typst-languagetool.report";
+/// Pause in typing before the local spellcheck of a changed file.
+const LOCAL_DELAY: std::time::Duration = std::time::Duration::from_millis(150);
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
 	eprintln!("Starting LSP server");
@@ -123,6 +301,14 @@ async fn main() -> anyhow::Result<()> {
 		)),
 
 		code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+		execute_command_provider: Some(ExecuteCommandOptions {
+			commands: vec![
+				SCOPE_COMMAND.into(),
+				FIXES_COMMAND.into(),
+				REPORT_COMMAND.into(),
+			],
+			..Default::default()
+		}),
 		..Default::default()
 	};
 
@@ -147,16 +333,78 @@ async fn main() -> anyhow::Result<()> {
 struct Options {
 	chunk_size: usize,
 	on_change: Option<std::time::Duration>,
+	recheck: Option<std::time::Duration>,
+	clear_on_close: bool,
+	draft_mode: Option<DraftMode>,
 	language_codes: HashMap<String, String>,
 	main: Option<PathBuf>,
+	rules: Rules,
+	#[cfg(feature = "spellcheck")]
+	spellcheck: Spellcheck,
+	quota: Option<Quota>,
+	metadata: bool,
+	scope: Vec<String>,
+	report_skipped: bool,
+	merge_adjacent: bool,
+	profiles: HashMap<String, Profile>,
+	category_severity: HashMap<String, Severity>,
+	rule_severity: HashMap<String, Severity>,
+}
+
+impl Options {
+	#[cfg(feature = "spellcheck")]
+	fn has_spellcheck(&self) -> bool {
+		self.spellcheck.is_empty().not()
+	}
+
+	#[cfg(not(feature = "spellcheck"))]
+	fn has_spellcheck(&self) -> bool {
+		false
+	}
+
+	#[cfg(feature = "spellcheck")]
+	fn local_spellcheck(&self, text: &str, lang: &str) -> Vec<Suggestion> {
+		self.spellcheck.check(text, lang)
+	}
+
+	#[cfg(not(feature = "spellcheck"))]
+	fn local_spellcheck(&self, _text: &str, _lang: &str) -> Vec<Suggestion> {
+		Vec::new()
+	}
+
+	fn severity(&self, diagnostic: &typst_languagetool::Diagnostic) -> Severity {
+		self.rule_severity
+			.get(&diagnostic.rule_id)
+			.or_else(|| self.category_severity.get(&diagnostic.category))
+			.copied()
+			.unwrap_or(diagnostic.severity)
+	}
 }
 
 struct State {
 	world: LtWorld,
-	cache: Cache,
+	cache: HashMap<PathBuf, Cache>,
 	lt: LanguageTool,
 	connection: Connection,
 	check: Option<CheckData>,
+	/// Spellcheck of the changed file, delayed while typing
+	local_check: Option<CheckData>,
+	recheck_time: Option<std::time::Instant>,
+	open_files: HashSet<PathBuf>,
+	/// Published diagnostics of every file
+	diagnostics: HashMap<Url, Vec<Diagnostic>>,
+	/// Language codes of the backend, `None` if they couldn't be requested
+	supported_languages: Option<Vec<String>>,
+	warned_languages: HashSet<String>,
+	/// Lines shown by the client for `draft_mode`
+	visible: HashMap<PathBuf, std::ops::Range<usize>>,
+	usage: Usage,
+	work_done_progress: bool,
+	request_id: i32,
+	/// Pending request to create the recheck progress
+	progress_request: Option<RequestId>,
+	/// Messages received while rechecking, handled before new ones
+	pending: VecDeque<Message>,
 	options: Options,
 }
 
@@ -164,11 +412,23 @@ struct CheckData {
 	check_time: std::time::Instant,
 	url: Url,
 	path: PathBuf,
+	mode: Mode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+	/// Check complete chunks
+	Full,
+	/// Only check changed paragraphs instead of complete chunks
+	Changed,
+	/// Use the local spellcheck for changed paragraphs instead of LanguageTool
+	Local,
 }
 
 enum Action {
 	Message(Message),
 	Check(CheckData),
+	Recheck,
 }
 
 impl State {
@@ -180,7 +440,12 @@ impl State {
 			eprintln!("Unknown option: {}", path);
 		})?;
 
-		let cache = Cache::new();
+		let work_done_progress = params
+			.capabilities
+			.window
+			.and_then(|window| window.work_done_progress)
+			.unwrap_or(false);
+		let cache = HashMap::new();
 
 		options.make_absolute();
 		eprintln!("Options: {:#?}", options);
@@ -190,49 +455,239 @@ impl State {
 
 		eprintln!("Compiling document");
 
-		Ok(Self {
+		let mut state = Self {
 			world,
 			cache,
 			lt,
 			connection,
 			check: None,
+			local_check: None,
+			recheck_time: None,
+			open_files: HashSet::new(),
+			diagnostics: HashMap::new(),
+			visible: HashMap::new(),
+			supported_languages: None,
+			warned_languages: HashSet::new(),
+			usage: Usage::default(),
+			work_done_progress,
+			request_id: 0,
+			progress_request: None,
+			pending: VecDeque::new(),
+
+			options: options.into_options(),
+		};
+		state.validate_languages().await?;
+		Ok(state)
+	}
 
-			options: Options {
-				on_change: options.on_change,
-				chunk_size: options.chunk_size,
-				language_codes: create_language_map(options.languages),
-				main: options.main,
+	async fn validate_languages(&mut self) -> anyhow::Result<()> {
+		self.supported_languages = match self.lt.supported_languages().await {
+			Ok(languages) => Some(languages),
+			Err(err) => {
+				eprintln!("Failed to get supported languages: {}", err);
+				None
 			},
-		})
+		};
+		self.warned_languages.clear();
+		let configured = self
+			.options
+			.language_codes
+			.values()
+			.cloned()
+			.collect::<Vec<_>>();
+		for lang in configured {
+			self.language_supported(&lang)?;
+		}
+		Ok(())
+	}
+
+	/// Warn once for every language, which is not supported by the backend.
+	fn language_supported(&mut self, lang: &str) -> anyhow::Result<bool> {
+		let Some(supported) = &self.supported_languages else {
+			return Ok(true);
+		};
+		if supported.iter().any(|code| code.eq_ignore_ascii_case(lang)) {
+			return Ok(true);
+		}
+		if self.warned_languages.insert(lang.into()) {
+			let message = match typst_languagetool::closest_language(lang, supported) {
+				Some(closest) => format!(
+					"Language \"{}\" is not supported by LanguageTool, use \"{}\" instead.",
+					lang, closest
+				),
+				None => format!("Language \"{}\" is not supported by LanguageTool.", lang),
+			};
+			eprintln!("{}", message);
+			let params = ShowMessageParams { typ: MessageType::WARNING, message };
+			send_notification::<ShowMessage>(&self.connection, params)?;
+		}
+		Ok(false)
 	}
 
 	pub async fn main_loop(mut self) -> anyhow::Result<()> {
 		eprintln!("Waiting for events");
 		loop {
 			match self.next_action()? {
-				Action::Message(msg) => self.message(msg).await?,
-				Action::Check(data) => self.check_change(&data.path, data.url).await?,
+				Action::Message(msg) => {
+					let activity = matches!(msg, Message::Response(_)).not();
+					self.message(msg).await?;
+					if activity {
+						self.schedule_recheck();
+					}
+				},
+				Action::Check(mut data) => {
+					let wait = match self.options.quota {
+						Some(quota) if data.mode != Mode::Local => self.usage.wait(quota),
+						_ => None,
+					};
+					let wait = match wait {
+						Some(time) => Some(time),
+						None => {
+							self.check_change(&data.path, data.url.clone(), data.mode)
+								.await?
+						},
+					};
+					if let Some(time) = wait {
+						eprintln!("Quota reached, delaying check");
+						data.check_time = time;
+						self.check = Some(data);
+					}
+				},
+				Action::Recheck => self.recheck().await?,
 			}
 		}
 	}
 
 	fn next_action(&mut self) -> anyhow::Result<Action> {
-		if let Some(last_change) = &self.check {
-			let msg = self
-				.connection
-				.receiver
-				.recv_deadline(last_change.check_time);
-			match msg {
-				Ok(msg) => Ok(Action::Message(msg)),
-				Err(RecvTimeoutError::Timeout) => Ok(Action::Check(self.check.take().unwrap())),
-				Err(err) => Err(err.into()),
-			}
-		} else {
+		if let Some(msg) = self.pending.pop_front() {
+			return Ok(Action::Message(msg));
+		}
+		let check_time = self.check.as_ref().map(|check| check.check_time);
+		let local_time = self.local_check.as_ref().map(|check| check.check_time);
+		let Some(deadline) = check_time
+			.into_iter()
+			.chain(local_time)
+			.chain(self.recheck_time)
+			.min()
+		else {
 			let msg = self.connection.receiver.recv()?;
-			Ok(Action::Message(msg))
+			return Ok(Action::Message(msg));
+		};
+		match self.connection.receiver.recv_deadline(deadline) {
+			Ok(msg) => Ok(Action::Message(msg)),
+			Err(RecvTimeoutError::Timeout) if local_time == Some(deadline) => {
+				Ok(Action::Check(self.local_check.take().unwrap()))
+			},
+			Err(RecvTimeoutError::Timeout) if check_time == Some(deadline) => {
+				Ok(Action::Check(self.check.take().unwrap()))
+			},
+			Err(RecvTimeoutError::Timeout) => {
+				self.recheck_time = None;
+				Ok(Action::Recheck)
+			},
+			Err(err) => Err(err.into()),
 		}
 	}
 
+	fn schedule_recheck(&mut self) {
+		self.recheck_time = self
+			.options
+			.recheck
+			.map(|duration| std::time::Instant::now() + duration);
+	}
+
+	async fn recheck(&mut self) -> anyhow::Result<()> {
+		let files = if self.options.main.is_some() {
+			self.world.project_files()
+		} else {
+			self.open_files.iter().cloned().collect()
+		};
+		eprintln!("Rechecking {} files", files.len());
+
+		let token = NumberOrString::String("typst-languagetool/recheck".into());
+		if self.work_done_progress && self.progress_request.is_none() {
+			self.request_id += 1;
+			let params = WorkDoneProgressCreateParams { token: token.clone() };
+			send_request::<WorkDoneProgressCreate>(&self.connection, self.request_id, params)?;
+			self.progress_request = Some(self.request_id.into());
+		}
+
+		// The progress is only reported after the client created it.
+		let mut progress = false;
+		for (idx, path) in files.iter().enumerate() {
+			if self.interrupted(&token, &mut progress)? {
+				eprintln!("Recheck interrupted");
+				break;
+			}
+			if progress {
+				let message = path.strip_prefix(self.world.root()).unwrap_or(path);
+				self.progress(
+					&token,
+					WorkDoneProgress::Report(WorkDoneProgressReport {
+						cancellable: Some(false),
+						message: Some(message.display().to_string()),
+						percentage: Some((idx * 100 / files.len()) as u32),
+					}),
+				)?;
+			}
+			let Ok(url) = Url::from_file_path(path) else {
+				continue;
+			};
+			if let Some(time) = self.check_change(path, url, Mode::Full).await? {
+				eprintln!("Quota reached, delaying recheck");
+				self.recheck_time = Some(time);
+				break;
+			}
+		}
+
+		if progress {
+			self.progress(
+				&token,
+				WorkDoneProgress::End(WorkDoneProgressEnd { message: None }),
+			)?;
+		}
+		Ok(())
+	}
+
+	/// Handle the queued responses, `true` if a request or notification is waiting.
+	/// Begins the progress, when the client acknowledges its creation.
+	fn interrupted(&mut self, token: &ProgressToken, progress: &mut bool) -> anyhow::Result<bool> {
+		while let Ok(msg) = self.connection.receiver.try_recv() {
+			let Message::Response(resp) = msg else {
+				self.pending.push_back(msg);
+				return Ok(true);
+			};
+			if self.progress_request.as_ref() != Some(&resp.id) {
+				eprintln!("Unknown response: {:?}", resp);
+				continue;
+			}
+			self.progress_request = None;
+			if resp.error.is_some() {
+				eprintln!("Progress not created: {:?}", resp.error);
+				continue;
+			}
+			*progress = true;
+			self.progress(
+				token,
+				WorkDoneProgress::Begin(WorkDoneProgressBegin {
+					title: "Checking project".into(),
+					cancellable: Some(false),
+					message: None,
+					percentage: Some(0),
+				}),
+			)?;
+		}
+		Ok(false)
+	}
+
+	fn progress(&self, token: &ProgressToken, value: WorkDoneProgress) -> anyhow::Result<()> {
+		let params = ProgressParams {
+			token: token.clone(),
+			value: ProgressParamsValue::WorkDone(value),
+		};
+		send_notification::<Progress>(&self.connection, params)
+	}
+
 	pub async fn message(&mut self, msg: Message) -> anyhow::Result<()> {
 		match msg {
 			Message::Request(req) => {
@@ -241,6 +696,11 @@ impl State {
 				}
 				self.request(req).await
 			},
+			// Acknowledged after the recheck was done or interrupted, a new one is created next time.
+			Message::Response(resp) if self.progress_request.as_ref() == Some(&resp.id) => {
+				self.progress_request = None;
+				Ok(())
+			},
 			Message::Response(resp) => {
 				eprintln!("Unknown response: {:?}", resp);
 				Ok(())
@@ -259,10 +719,185 @@ impl State {
 			Err(err @ ExtractError::JsonError { .. }) => return Err(err.into()),
 			Err(ExtractError::MethodMismatch(req)) => req,
 		};
+		let req = match cast_request::<StatisticsRequest>(req) {
+			Ok((id, _)) => {
+				let statistics = self.usage.statistics(self.options.quota);
+				send_response::<StatisticsRequest>(&self.connection, id, statistics)?;
+				return Ok(());
+			},
+			Err(err @ ExtractError::JsonError { .. }) => return Err(err.into()),
+			Err(ExtractError::MethodMismatch(req)) => req,
+		};
+		let req = match cast_request::<VisibleRange>(req) {
+			Ok((id, params)) => {
+				send_response::<VisibleRange>(&self.connection, id, ())?;
+				return self.visible_range(params);
+			},
+			Err(err @ ExtractError::JsonError { .. }) => return Err(err.into()),
+			Err(ExtractError::MethodMismatch(req)) => req,
+		};
+		let req = match cast_request::<ExecuteCommand>(req) {
+			Ok((id, params)) => {
+				let command = params.command.clone();
+				match self.execute_command(params).await {
+					Ok(result) => send_response::<ExecuteCommand>(&self.connection, id, result)?,
+					Err(err) => {
+						eprintln!("Command {} failed: {:#}", command, err);
+						send_error(&self.connection, id, format!("{:#}", err))?;
+					},
+				}
+				return Ok(());
+			},
+			Err(err @ ExtractError::JsonError { .. }) => return Err(err.into()),
+			Err(ExtractError::MethodMismatch(req)) => req,
+		};
 		eprintln!("Unknown request: {:?}", req);
 		Ok(())
 	}
 
+	fn visible_range(&mut self, params: VisibleRangeParams) -> anyhow::Result<()> {
+		let Ok(path) = params.text_document.uri.to_file_path() else {
+			return Ok(());
+		};
+		let lines = params.range.start.line as usize..params.range.end.line as usize;
+		let changed = self.visible.get(&path) != Some(&lines);
+		self.visible.insert(path.clone(), lines);
+		if changed.not() || matches!(self.options.draft_mode, Some(DraftMode::Visible(_))).not() {
+			return Ok(());
+		}
+		let mode = match &self.check {
+			Some(check) if check.path == path => check.mode,
+			_ => Mode::Changed,
+		};
+		self.check = Some(CheckData {
+			check_time: std::time::Instant::now() + self.options.on_change.unwrap_or_default(),
+			url: params.text_document.uri,
+			path,
+			mode,
+		});
+		Ok(())
+	}
+
+	async fn execute_command(
+		&mut self,
+		params: ExecuteCommandParams,
+	) -> anyhow::Result<Option<Value>> {
+		let arguments = params
+			.arguments
+			.into_iter()
+			.filter_map(|argument| argument.as_str().map(String::from))
+			.collect::<Vec<_>>();
+		match params.command.as_str() {
+			SCOPE_COMMAND => {
+				self.options.scope = arguments;
+				eprintln!("Scope: {:?}", self.options.scope);
+				self.recheck().await?;
+				Ok(None)
+			},
+			FIXES_COMMAND => {
+				let edit = self.preferred_fixes(&arguments);
+				Ok(Some(serde_json::to_value(edit)?))
+			},
+			REPORT_COMMAND => {
+				let format = match arguments.first().map(String::as_str) {
+					Some("markdown") => report::Format::Markdown,
+					_ => report::Format::Html,
+				};
+				let output = arguments
+					.get(1)
+					.map(|output| report_path(self.world.root(), output))
+					.transpose()?;
+				let report = self.report(format);
+				if let Some(output) = output {
+					std::fs::write(&output, &report)
+						.with_context(|| format!("Failed to write {}", output.display()))?;
+					eprintln!("Report written to {}", output.display());
+				}
+				Ok(Some(Value::String(report)))
+			},
+			command => {
+				eprintln!("Unknown command: {}", command);
+				Ok(None)
+			},
+		}
+	}
+
+	/// Preferred replacement of every diagnostic with one of the categories, all without categories.
+	/// Fixes overlapping a previous fix are left out.
+	fn preferred_fixes(&self, categories: &[String]) -> WorkspaceEdit {
+		let mut changes = HashMap::new();
+		for (url, diagnostics) in &self.diagnostics {
+			let mut fixes = diagnostics
+				.iter()
+				.filter_map(|diagnostic| {
+					let data = diagnostic.data.clone()?;
+					let data = serde_json::from_value::<DiagnosticData>(data).ok()?;
+					if categories.is_empty().not() && categories.contains(&data.category).not() {
+						return None;
+					}
+					data.fixes
+						.into_iter()
+						.next()
+						.filter(|edits| edits.is_empty().not())
+				})
+				.collect::<Vec<_>>();
+			fixes.sort_by_key(|edits| edits[0].range.start);
+
+			let mut edits = Vec::<TextEdit>::new();
+			for fix in fixes {
+				if edits
+					.last()
+					.is_some_and(|last| last.range.end > fix[0].range.start)
+				{
+					continue;
+				}
+				edits.extend(fix);
+			}
+			if edits.is_empty().not() {
+				changes.insert(url.clone(), edits);
+			}
+		}
+		eprintln!("Preferred fixes for {} files", changes.len());
+		WorkspaceEdit {
+			changes: Some(changes),
+			..Default::default()
+		}
+	}
+
+	/// Report of the current diagnostics, files which were not checked yet are left out.
+	fn report(&self, format: report::Format) -> String {
+		let mut files = Vec::new();
+		for (url, diagnostics) in &self.diagnostics {
+			let Ok(path) = url.to_file_path() else {
+				continue;
+			};
+			// Deleted files and files outside of the root have no source.
+			let Some(file_id) = self.world.file_id(&path) else {
+				continue;
+			};
+			let Ok(source) = self.world.with_main(path.clone()).source(file_id) else {
+				continue;
+			};
+			let diagnostics = diagnostics
+				.iter()
+				.filter_map(|diagnostic| report_diagnostic(&source, diagnostic))
+				.collect::<Vec<_>>();
+			let name = path.strip_prefix(self.world.root()).unwrap_or(&path);
+			files.push((name.display().to_string(), source, diagnostics));
+		}
+		files.sort_by(|a, b| a.0.cmp(&b.0));
+		let files = files
+			.iter()
+			.map(|(name, source, diagnostics)| report::FileReport {
+				name: name.clone(),
+				source,
+				diagnostics: diagnostics.clone(),
+			})
+			.collect::<Vec<_>>();
+		eprintln!("Report for {} files", files.len());
+		report::render(&files, format)
+	}
+
 	async fn code_action(
 		&self,
 		params: CodeActionParams,
@@ -276,18 +911,20 @@ impl State {
 			return Ok(None);
 		};
 
-		let replacements = match serde_json::from_value::<Vec<String>>(data.clone()) {
-			Ok(r) => r,
+		let data = match serde_json::from_value::<DiagnosticData>(data.clone()) {
+			Ok(data) => data,
 			Err(err) => {
 				eprintln!("{}", err);
 				return Ok(None);
 			},
 		};
 
-		for (i, value) in replacements.into_iter().enumerate() {
+		for (i, (value, edits)) in data.replacements.into_iter().zip(data.fixes).enumerate() {
+			if edits.is_empty() {
+				continue;
+			}
 			let title = format!("Replace with \"{}\"", value);
-			let replace = TextEdit { range: diagnostic.range, new_text: value };
-			let edit = [(params.text_document.uri.clone(), vec![replace])]
+			let edit = [(params.text_document.uri.clone(), edits)]
 				.into_iter()
 				.collect();
 
@@ -358,6 +995,7 @@ impl State {
 			check_time: std::time::Instant::now(),
 			url: params.text_document.uri,
 			path,
+			mode: Mode::Full,
 		});
 		Ok(())
 	}
@@ -366,10 +1004,12 @@ impl State {
 		let path = params.text_document.uri.to_file_path().unwrap();
 		eprintln!("Open {}", path.display());
 		self.world.use_shadow_file(&path, params.text_document.text);
+		self.open_files.insert(path.clone());
 		self.check = Some(CheckData {
 			check_time: std::time::Instant::now(),
 			url: params.text_document.uri,
 			path,
+			mode: Mode::Full,
 		});
 		Ok(())
 	}
@@ -378,6 +1018,27 @@ impl State {
 		let path = &params.text_document.uri.to_file_path().unwrap();
 		eprintln!("Close {}", path.display());
 		self.world.use_original_file(&path);
+		self.open_files.remove(path);
+		if self.options.clear_on_close.not() {
+			return Ok(());
+		}
+		if self.check.as_ref().is_some_and(|check| check.path == *path) {
+			self.check = None;
+		}
+		if self
+			.local_check
+			.as_ref()
+			.is_some_and(|check| check.path == *path)
+		{
+			self.local_check = None;
+		}
+		self.diagnostics.remove(&params.text_document.uri);
+		let params = PublishDiagnosticsParams {
+			uri: params.text_document.uri,
+			version: None,
+			diagnostics: Vec::new(),
+		};
+		send_notification::<PublishDiagnostics>(&self.connection, params)?;
 		Ok(())
 	}
 
@@ -400,32 +1061,56 @@ impl State {
 			}
 		}
 
+		if self.options.has_spellcheck() {
+			self.local_check = Some(CheckData {
+				check_time: std::time::Instant::now() + LOCAL_DELAY,
+				url: params.text_document.uri.clone(),
+				path: path.clone(),
+				mode: Mode::Local,
+			});
+		}
+
 		let Some(duration) = self.options.on_change else {
 			return Ok(());
 		};
+		let mode = match &self.check {
+			Some(check) if check.path == path => check.mode,
+			_ => Mode::Changed,
+		};
 		self.check = Some(CheckData {
 			check_time: std::time::Instant::now() + duration,
 			url: params.text_document.uri,
 			path,
+			mode,
 		});
 		Ok(())
 	}
 
-	async fn check_change(&mut self, path: &Path, url: Url) -> anyhow::Result<()> {
+	/// Check and publish the diagnostics, returns the time to retry at when the quota is reached.
+	async fn check_change(
+		&mut self,
+		path: &Path,
+		url: Url,
+		mode: Mode,
+	) -> anyhow::Result<Option<std::time::Instant>> {
 		eprintln!("Checking: {}", path.display());
 
-		let diagnostics = match self.get_diagnostics(path).await {
+		let diagnostics = match self.get_diagnostics(path, mode).await {
 			Ok(d) => d,
 			Err(err) => {
+				if let Some(QuotaReached(time)) = err.downcast_ref() {
+					return Ok(Some(*time));
+				}
 				eprintln!("{:?}", err);
-				return Ok(());
+				return Ok(None);
 			},
 		};
 		let l = diagnostics.len();
+		self.diagnostics.insert(url.clone(), diagnostics.clone());
 		let params = PublishDiagnosticsParams { uri: url, version: None, diagnostics };
 		send_notification::<PublishDiagnostics>(&self.connection, params)?;
 		eprintln!("{} Diagnostics send", l);
-		Ok(())
+		Ok(None)
 	}
 
 	async fn config_change(&mut self, params: DidChangeConfigurationParams) -> anyhow::Result<()> {
@@ -451,21 +1136,24 @@ impl State {
 			},
 		};
 
-		if let Some(root) = options.root {
-			self.world = LtWorld::new(root);
+		if let Some(root) = &options.root {
+			self.world = LtWorld::new(root.clone());
 		}
 
-		self.options = Options {
-			on_change: options.on_change,
-			chunk_size: options.chunk_size,
-			language_codes: create_language_map(options.languages),
-			main: options.main,
-		};
+		self.options = options.into_options();
+		self.validate_languages().await?;
 
 		Ok(())
 	}
 
-	async fn get_diagnostics(&mut self, path: &Path) -> anyhow::Result<Vec<Diagnostic>> {
+	async fn get_diagnostics(
+		&mut self,
+		path: &Path,
+		mode: Mode,
+	) -> anyhow::Result<Vec<Diagnostic>> {
+		let Some(file_id) = self.world.file_id(path) else {
+			return Ok(Vec::new());
+		};
 		let world = self
 			.world
 			.with_main(self.options.main.clone().unwrap_or_else(|| path.to_owned()));
@@ -481,39 +1169,158 @@ impl State {
 			},
 		};
 
-		let Some(file_id) = self.world.file_id(path) else {
-			return Ok(Vec::new());
-		};
 		eprintln!("Converting");
-		let paragraphs =
+		let source = world.source(file_id)?;
+		let (mut paragraphs, skipped) =
 			typst_languagetool::convert::document(&doc, self.options.chunk_size, file_id);
+		for element in skipped
+			.iter()
+			.filter(|element| element.is_reported(&source))
+		{
+			if let Some(range) = source.range(element.span) {
+				let (line, column) = byte_to_position(&source, range.start);
+				eprintln!("Skipped {} at {}:{}", element.kind, line + 1, column + 1);
+			}
+		}
+		let profiles = profile::active(&self.options.profiles, &source, &world.main());
+		let overlays = profiles.iter().any(|profile| profile.overlays);
+		if overlays {
+			typst_languagetool::convert::remove_overlays(&mut paragraphs);
+		}
+		let excluded = [
+			profile::ignored(&source, &profiles),
+			profile::notes(&source, &profiles),
+		]
+		.concat();
+		typst_languagetool::convert::remove_ranges(&source, &mut paragraphs, &excluded);
+		if self.options.metadata {
+			paragraphs.extend(typst_languagetool::convert::metadata(&source, &paragraphs));
+		}
+		paragraphs.extend(typst_languagetool::convert::notes(
+			&source,
+			&paragraphs,
+			&profiles,
+		));
+		let scope = (self.options.scope.is_empty().not())
+			.then(|| scope::ranges(&source, &self.options.scope));
+		let in_scope = |range: &std::ops::Range<usize>| {
+			scope.as_ref().is_none_or(|scope| {
+				scope
+					.iter()
+					.any(|x| x.start < range.end && range.start < x.end)
+			})
+		};
 		let mut collector = typst_languagetool::FileCollector::new(file_id, &world);
+		if overlays {
+			collector.deduplicate();
+		}
+		if self.options.report_skipped {
+			collector.add_skipped(&skipped);
+		}
+		let cache = self.cache.remove(path).unwrap_or_else(Cache::new);
 		let mut next_cache = Cache::new();
+		let local = self.options.rules.check(&source, &paragraphs);
 		let l = paragraphs.len();
 		eprintln!("Checking {} paragraphs", l);
-		for (idx, (text, mapping)) in paragraphs.into_iter().enumerate() {
+		let mut remaining = match self.options.draft_mode {
+			Some(DraftMode::First(paragraphs)) => Some(paragraphs),
+			_ => None,
+		};
+		let near = match self.options.draft_mode {
+			Some(DraftMode::Visible(margin)) => {
+				let lines = self.visible.get(path).cloned().unwrap_or(0..0);
+				let start = source
+					.line_to_byte(lines.start.saturating_sub(margin))
+					.unwrap_or(0);
+				let end = source
+					.line_to_byte(lines.end + margin + 1)
+					.unwrap_or(source.len_bytes());
+				Some(start..end)
+			},
+			_ => None,
+		};
+		for (idx, ((mut text, mapping), mut local)) in paragraphs.into_iter().zip(local).enumerate()
+		{
+			if scope
+				.as_ref()
+				.is_some_and(|scope| mapping.intersects(&source, scope).not())
+			{
+				continue;
+			}
+			let mut cached = self.cached(&cache, &text);
+			let count = typst_languagetool::convert::paragraphs(&text).len();
+			if cached.is_none() {
+				if near.as_ref().is_some_and(|near| {
+					mapping
+						.intersects(&source, std::slice::from_ref(near))
+						.not()
+				}) {
+					continue;
+				}
+				match remaining {
+					Some(0) => continue,
+					Some(remaining) if remaining < count => {
+						let (offset, _) = typst_languagetool::convert::paragraphs(&text)[remaining];
+						truncate_utf16(&mut text, offset);
+						local.retain(|suggestion| suggestion.end <= offset);
+						cached = self.cached(&cache, &text);
+					},
+					_ => {},
+				}
+			}
+			if let Some(remaining) = &mut remaining {
+				*remaining = remaining.saturating_sub(count);
+			}
 			let lang = self
 				.options
 				.language_codes
 				.get(mapping.short_language())
 				.map(|x| x.clone())
 				.unwrap_or(mapping.long_language());
-			let suggestions = if let Some(suggestions) = self.cache.get(&text) {
-				suggestions
-			} else {
-				eprintln!("Checking {}/{}", idx + 1, l);
-				self.lt.check_text(lang, &text).await?
+			let found = match cached {
+				Some(suggestions) => {
+					next_cache.insert_chunk(&text, &suggestions);
+					Ok(suggestions)
+				},
+				None if self.language_supported(&lang)?.not() => Ok(Vec::new()),
+				None if mode != Mode::Full => {
+					eprintln!("Checking changed paragraphs {}/{}", idx + 1, l);
+					self.check_paragraphs(&cache, &mut next_cache, &text, &lang, mode)
+						.await
+				},
+				None => {
+					eprintln!("Checking {}/{}", idx + 1, l);
+					let suggestions = self.check_text(lang.clone(), &text).await;
+					if let Ok(suggestions) = &suggestions {
+						next_cache.insert_chunk(&text, suggestions);
+					}
+					suggestions
+				},
 			};
-			collector.add(&suggestions, mapping);
-			next_cache.insert(text, suggestions);
+			let mut found = match found {
+				Ok(found) => found,
+				Err(err) => {
+					// Keep the finished checks, so the next attempt only checks the rest.
+					next_cache.merge(cache);
+					self.cache.insert(path.to_owned(), next_cache);
+					return Err(err);
+				},
+			};
+			found.extend(local);
+			self.options.rules.filter(&text, &lang, &mut found);
+			if self.options.merge_adjacent {
+				typst_languagetool::merge_adjacent(&text, &mut found);
+			}
+			collector.add(&found, &mapping);
 		}
-		self.cache = next_cache;
+		self.cache.insert(path.to_owned(), next_cache);
 		eprintln!("Generating diagnostics");
 
 		let (source, diagnostics) = collector.finish();
 
 		let diagnostics = diagnostics
 			.into_iter()
+			.filter(|diagnostic| in_scope(&diagnostic.locations[0]))
 			.map(|diagnostic| {
 				let (start_line, start_column) =
 					byte_to_position(&source, diagnostic.locations[0].start);
@@ -530,20 +1337,84 @@ impl State {
 							character: end_column as u32,
 						},
 					},
-					severity: Some(DiagnosticSeverity::INFORMATION),
+					severity: Some(diagnostic_severity(self.options.severity(&diagnostic))),
 					code: Some(NumberOrString::String(diagnostic.rule_id)),
 					code_description: None,
 					source: None,
 					message: diagnostic.message,
 					related_information: None,
 					tags: None,
-					data: serde_json::to_value(diagnostic.replacements).ok(),
+					data: serde_json::to_value(DiagnosticData {
+						fixes: diagnostic
+							.fixes
+							.iter()
+							.map(|edits| text_edits(&source, edits))
+							.collect(),
+						replacements: diagnostic.replacements,
+						category: diagnostic.category,
+						rule_description: diagnostic.rule_description,
+					})
+					.ok(),
 				}
 			})
 			.collect();
 
 		Ok(diagnostics)
 	}
+
+	async fn check_text(&mut self, lang: String, text: &str) -> anyhow::Result<Vec<Suggestion>> {
+		let Some(quota) = self.options.quota else {
+			return self.lt.check_text(lang, text).await;
+		};
+		if let Some(time) = self.usage.wait(quota) {
+			return Err(QuotaReached(time).into());
+		}
+		self.usage.record(text.len());
+		if let Some(message) = self.usage.warning(quota) {
+			eprintln!("{}", message);
+			let params = ShowMessageParams { typ: MessageType::WARNING, message };
+			send_notification::<ShowMessage>(&self.connection, params)?;
+		}
+		self.lt.check_text(lang, text).await
+	}
+
+	fn cached(&self, cache: &Cache, text: &str) -> Option<Vec<Suggestion>> {
+		cache
+			.get(text)
+			.or_else(|| self.cache.values().find_map(|cache| cache.get(text)))
+	}
+
+	/// Check every paragraph on its own, unchanged paragraphs are already cached.
+	async fn check_paragraphs(
+		&mut self,
+		cache: &Cache,
+		next_cache: &mut Cache,
+		text: &str,
+		lang: &str,
+		mode: Mode,
+	) -> anyhow::Result<Vec<Suggestion>> {
+		let mut suggestions = Vec::new();
+		for (offset, paragraph) in typst_languagetool::convert::paragraphs(text) {
+			let shift = |mut suggestion: Suggestion| {
+				suggestion.start += offset;
+				suggestion.end += offset;
+				suggestion
+			};
+			let found = match self.cached(cache, paragraph) {
+				Some(found) => found,
+				// not cached, so the next check with LanguageTool replaces it
+				None if mode == Mode::Local => {
+					let found = self.options.local_spellcheck(paragraph, lang);
+					suggestions.extend(found.into_iter().map(shift));
+					continue;
+				},
+				None => self.check_text(lang.into(), paragraph).await?,
+			};
+			suggestions.extend(found.iter().cloned().map(shift));
+			next_cache.insert(paragraph.into(), found);
+		}
+		Ok(suggestions)
+	}
 }
 
 fn cast_request<R>(req: Request) -> Result<(RequestId, R::Params), ExtractError<Request>>
@@ -562,7 +1433,6 @@ where
 	not.extract(N::METHOD)
 }
 
-#[allow(dead_code)]
 fn send_request<R>(connection: &Connection, id: i32, params: R::Params) -> anyhow::Result<()>
 where
 	R: lsp_types::request::Request,
@@ -582,6 +1452,38 @@ where
 	Ok(())
 }
 
+fn send_error(connection: &Connection, id: RequestId, message: String) -> anyhow::Result<()> {
+	let message = Message::Response(Response::new_err(
+		id,
+		ErrorCode::RequestFailed as i32,
+		message,
+	));
+	connection.sender.send(message)?;
+	Ok(())
+}
+
+/// Path for a report file relative to the root, paths leaving the root are rejected.
+fn report_path(root: &Path, output: &str) -> anyhow::Result<PathBuf> {
+	let relative = Path::new(output);
+	let inside = relative
+		.components()
+		.all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+	if inside.not() || relative.file_name().is_none() {
+		anyhow::bail!("Report path must be relative to the root: {}", output);
+	}
+	let output = root.join(relative);
+	// Symbolic links in the path can still point outside of the root.
+	let parent = output
+		.parent()
+		.unwrap_or(root)
+		.canonicalize()
+		.with_context(|| format!("Invalid report path: {}", output.display()))?;
+	if parent.starts_with(root.canonicalize()?).not() {
+		anyhow::bail!("Report path is outside of the root: {}", output.display());
+	}
+	Ok(output)
+}
+
 fn send_notification<N>(connection: &Connection, params: N::Params) -> anyhow::Result<()>
 where
 	N: lsp_types::notification::Notification,
@@ -601,13 +1503,128 @@ impl Cache {
 		Self { cache: HashMap::new() }
 	}
 
-	pub fn get(&mut self, text: &str) -> Option<Vec<Suggestion>> {
-		self.cache.remove(text)
+	pub fn get(&self, text: &str) -> Option<Vec<Suggestion>> {
+		self.cache.get(text).cloned()
 	}
 
 	pub fn insert(&mut self, text: String, suggestions: Vec<Suggestion>) {
 		self.cache.insert(text, suggestions);
 	}
+
+	/// Add the entries of the other cache, which are missing in this one.
+	pub fn merge(&mut self, other: Cache) {
+		for (text, suggestions) in other.cache {
+			self.cache.entry(text).or_insert(suggestions);
+		}
+	}
+
+	/// Suggestions for a chunk and each of its paragraphs, so later changes of the chunk only
+	/// check the changed paragraphs.
+	pub fn insert_chunk(&mut self, text: &str, suggestions: &[Suggestion]) {
+		self.insert_paragraphs(text, suggestions);
+		self.insert(text.into(), suggestions.to_vec());
+	}
+
+	/// Split the suggestions for a chunk into its paragraphs.
+	pub fn insert_paragraphs(&mut self, text: &str, suggestions: &[Suggestion]) {
+		for (offset, paragraph) in typst_languagetool::convert::paragraphs(text) {
+			let end = offset + paragraph.encode_utf16().count();
+			let suggestions = suggestions
+				.iter()
+				.filter(|suggestion| offset <= suggestion.start && suggestion.end <= end)
+				.cloned()
+				.map(|mut suggestion| {
+					suggestion.start -= offset;
+					suggestion.end -= offset;
+					suggestion
+				})
+				.collect();
+			self.insert(paragraph.into(), suggestions);
+		}
+	}
+}
+
+fn diagnostic_severity(severity: Severity) -> DiagnosticSeverity {
+	match severity {
+		Severity::Error => DiagnosticSeverity::ERROR,
+		Severity::Warning => DiagnosticSeverity::WARNING,
+		Severity::Information => DiagnosticSeverity::INFORMATION,
+		Severity::Hint => DiagnosticSeverity::HINT,
+	}
+}
+
+/// Replacements of a diagnostic with the edits to apply them.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct DiagnosticData {
+	replacements: Vec<String>,
+	fixes: Vec<Vec<TextEdit>>,
+	category: String,
+	rule_description: String,
+}
+
+/// Published diagnostic with the information of the report.
+fn report_diagnostic(
+	source: &Source,
+	diagnostic: &Diagnostic,
+) -> Option<typst_languagetool::Diagnostic> {
+	let data = serde_json::from_value::<DiagnosticData>(diagnostic.data.clone()?).ok()?;
+	let byte = |position: lsp_types::Position| {
+		source.line_column_to_byte(position.line as usize, position.character as usize)
+	};
+	let rule_id = match &diagnostic.code {
+		Some(NumberOrString::String(code)) => code.clone(),
+		_ => String::new(),
+	};
+	let range = byte(diagnostic.range.start)?..byte(diagnostic.range.end)?;
+	Some(typst_languagetool::Diagnostic {
+		locations: vec![range],
+		message: diagnostic.message.clone(),
+		replacements: data.replacements,
+		fixes: Vec::new(),
+		rule_description: data.rule_description,
+		rule_id,
+		category: data.category,
+		severity: match diagnostic.severity {
+			Some(DiagnosticSeverity::ERROR) => Severity::Error,
+			Some(DiagnosticSeverity::WARNING) => Severity::Warning,
+			Some(DiagnosticSeverity::HINT) => Severity::Hint,
+			_ => Severity::Information,
+		},
+	})
+}
+
+fn text_edits(source: &Source, edits: &[typst_languagetool::fix::Edit]) -> Vec<TextEdit> {
+	edits
+		.iter()
+		.map(|edit| {
+			let (start_line, start_column) = byte_to_position(source, edit.range.start);
+			let (end_line, end_column) = byte_to_position(source, edit.range.end);
+			TextEdit {
+				range: Range {
+					start: lsp_types::Position {
+						line: start_line as u32,
+						character: start_column as u32,
+					},
+					end: lsp_types::Position {
+						line: end_line as u32,
+						character: end_column as u32,
+					},
+				},
+				new_text: edit.text.clone(),
+			}
+		})
+		.collect()
+}
+
+fn truncate_utf16(text: &mut String, offset: usize) {
+	let mut position = 0;
+	for (idx, c) in text.char_indices() {
+		if position >= offset {
+			text.truncate(idx);
+			return;
+		}
+		position += c.len_utf16();
+	}
 }
 
 fn byte_to_position(source: &Source, index: usize) -> (usize, usize) {
@@ -617,3 +1634,219 @@ fn byte_to_position(source: &Source, index: usize) -> (usize, usize) {
 	let column = head.chars().count();
 	(line, column)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn truncated(text: &str, offset: usize) -> String {
+		let mut text = text.to_string();
+		truncate_utf16(&mut text, offset);
+		text
+	}
+
+	#[test]
+	fn truncate_at_utf16_offsets() {
+		assert_eq!(truncated("hello", 3), "hel");
+		assert_eq!(truncated("hello", 0), "");
+		assert_eq!(truncated("hello", 10), "hello");
+		assert_eq!(truncated("äöü", 2), "äö");
+		assert_eq!(truncated("😀a", 2), "😀");
+		// Offsets inside a surrogate pair keep the character.
+		assert_eq!(truncated("😀a", 1), "😀");
+	}
+
+	#[test]
+	fn report_paths_stay_in_the_root() {
+		let root = std::env::temp_dir().join(format!("lt-report-{}", std::process::id()));
+		std::fs::create_dir_all(root.join("out")).unwrap();
+		assert_eq!(
+			report_path(&root, "out/report.html").unwrap(),
+			root.join("out/report.html")
+		);
+		assert!(report_path(&root, "./report.md").is_ok());
+		for output in [
+			"../report.html",
+			"out/../../report.html",
+			"/tmp/report.html",
+			"",
+			".",
+		] {
+			assert!(report_path(&root, output).is_err(), "{}", output);
+		}
+		assert!(report_path(&root, "missing/report.html").is_err());
+		std::fs::remove_dir_all(&root).unwrap();
+	}
+
+	/// LanguageTool server without findings, which counts the checked texts.
+	#[cfg(feature = "remote-server")]
+	fn languagetool() -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+		use std::io::{BufRead, Read, Write};
+		use std::sync::atomic::Ordering;
+
+		let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		let port = listener.local_addr().unwrap().port().to_string();
+		let checks = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+		let counter = checks.clone();
+		std::thread::spawn(move || {
+			for mut stream in listener.incoming().flatten() {
+				let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+				let mut request = String::new();
+				let mut length = 0;
+				let mut line = String::new();
+				while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+					if let Some((name, value)) = line.split_once(':') {
+						if name.eq_ignore_ascii_case("content-length") {
+							length = value.trim().parse().unwrap();
+						}
+					}
+					request.push_str(&line);
+					line.clear();
+				}
+				reader.read_exact(&mut vec![0; length]).unwrap();
+				let body = if request.contains("/check") {
+					counter.fetch_add(1, Ordering::SeqCst);
+					r#"{"language": {"code": "en-US", "name": "English", "detectedLanguage": {"code": "en-US", "name": "English"}}, "matches": [], "software": {"apiVersion": 1, "buildDate": "", "name": "LanguageTool", "premium": false, "status": "", "version": "6"}}"#
+				} else {
+					r#"[{"name": "English", "code": "en", "longCode": "en-GB"}]"#
+				};
+				let _ = write!(
+					stream,
+					"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+					body.len(),
+					body
+				);
+			}
+		});
+		(port, checks)
+	}
+
+	/// State for a project with only `main.typ`, the client side of the connection has to be kept.
+	#[cfg(feature = "remote-server")]
+	async fn project(name: &str, port: &str, options: Value) -> (State, Connection, PathBuf) {
+		let root = std::env::temp_dir().join(format!("lt-{}-{}", name, std::process::id()));
+		std::fs::create_dir_all(&root).unwrap();
+		let path = root.join("main.typ");
+		std::fs::write(&path, "").unwrap();
+		let mut init = serde_json::json!({
+			"host": "http://127.0.0.1",
+			"port": port,
+			"root": root,
+			"chunk_size": 30,
+		});
+		init.as_object_mut()
+			.unwrap()
+			.extend(options.as_object().unwrap().clone());
+		let params = serde_json::json!({
+			"processId": null,
+			"rootUri": null,
+			"capabilities": {},
+			"initializationOptions": init,
+		});
+		let (connection, client) = Connection::memory();
+		let state = State::new(connection, params).await.unwrap();
+		(state, client, path)
+	}
+
+	#[cfg(feature = "remote-server")]
+	#[tokio::test]
+	async fn report_skips_files_without_source() {
+		let (port, _) = languagetool();
+		let (mut state, _client, path) = project("sources", &port, serde_json::json!({})).await;
+		state
+			.world
+			.use_shadow_file(&path, "This is teh test.".into());
+		let data = serde_json::json!({
+			"replacements": ["the"],
+			"fixes": [],
+			"category": "TYPOS",
+			"rule_description": "Spelling",
+		});
+		let diagnostic = Diagnostic {
+			range: lsp_types::Range::new(Position::new(0, 8), Position::new(0, 11)),
+			message: "Possible typo".into(),
+			data: Some(data),
+			..Default::default()
+		};
+		let root = path.parent().unwrap();
+		let outside = std::env::temp_dir().join(format!("lt-outside-{}.typ", std::process::id()));
+		std::fs::write(&outside, "This is teh test.").unwrap();
+		for path in [path.clone(), root.join("deleted.typ"), outside.clone()] {
+			let url = Url::from_file_path(path).unwrap();
+			state.diagnostics.insert(url, vec![diagnostic.clone()]);
+		}
+		let report = state.report(report::Format::Markdown);
+		assert!(report.contains("main.typ"));
+		assert!(report.contains("deleted.typ").not());
+		assert!(report.contains("lt-outside").not());
+		std::fs::remove_file(&outside).unwrap();
+		std::fs::remove_dir_all(root).unwrap();
+	}
+
+	#[cfg(feature = "remote-server")]
+	#[tokio::test]
+	async fn changes_check_only_changed_paragraphs() {
+		use std::sync::atomic::Ordering;
+
+		let (port, checks) = languagetool();
+		let (mut state, _client, path) = project("changes", &port, serde_json::json!({})).await;
+		let mut paragraphs = [
+			"Alpha one here.",
+			"Alpha two here.",
+			"Beta one here.",
+			"Beta two here.",
+		];
+		state.world.use_shadow_file(&path, paragraphs.join("\n\n"));
+		state.get_diagnostics(&path, Mode::Full).await.unwrap();
+		assert_eq!(checks.load(Ordering::SeqCst), 2);
+
+		paragraphs[0] = "Alpha one changed.";
+		state.world.use_shadow_file(&path, paragraphs.join("\n\n"));
+		state.get_diagnostics(&path, Mode::Changed).await.unwrap();
+		assert_eq!(checks.load(Ordering::SeqCst), 3);
+
+		paragraphs[3] = "Beta two changed.";
+		state.world.use_shadow_file(&path, paragraphs.join("\n\n"));
+		state.get_diagnostics(&path, Mode::Changed).await.unwrap();
+		assert_eq!(checks.load(Ordering::SeqCst), 4);
+		std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+	}
+
+	#[cfg(feature = "remote-server")]
+	#[tokio::test]
+	async fn quota_keeps_finished_checks() {
+		use std::sync::atomic::Ordering;
+
+		let (port, checks) = languagetool();
+		let options =
+			serde_json::json!({ "chunk_size": 10, "quota": { "requests": 1, "bytes": 1000 } });
+		let (mut state, _client, path) = project("quota", &port, options).await;
+		state
+			.world
+			.use_shadow_file(&path, "Alpha one here.\n\nBeta one here.".into());
+		let err = state.get_diagnostics(&path, Mode::Full).await.unwrap_err();
+		assert!(err.downcast_ref::<QuotaReached>().is_some());
+		assert_eq!(checks.load(Ordering::SeqCst), 1);
+		state.usage = Usage::default();
+		state.get_diagnostics(&path, Mode::Full).await.unwrap();
+		assert_eq!(checks.load(Ordering::SeqCst), 2);
+		std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+	}
+
+	#[cfg(feature = "remote-server")]
+	#[tokio::test]
+	async fn draft_boundary_stays_cached() {
+		use std::sync::atomic::Ordering;
+
+		let (port, checks) = languagetool();
+		let options = serde_json::json!({ "chunk_size": 40, "draft_mode": { "first": 1 } });
+		let (mut state, _client, path) = project("draft", &port, options).await;
+		let text = "Alpha one here.\n\nAlpha two here.\n\nBeta one here.";
+		state.world.use_shadow_file(&path, text.into());
+		state.get_diagnostics(&path, Mode::Full).await.unwrap();
+		assert_eq!(checks.load(Ordering::SeqCst), 1);
+		state.get_diagnostics(&path, Mode::Full).await.unwrap();
+		assert_eq!(checks.load(Ordering::SeqCst), 1);
+		std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+	}
+}
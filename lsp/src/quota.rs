@@ -0,0 +1,133 @@
+use std::{
+	collections::VecDeque,
+	fmt,
+	ops::Not,
+	time::{Duration, Instant, SystemTime},
+};
+
+const MINUTE: Duration = Duration::from_secs(60);
+/// Fraction of the limits, after which a warning is shown.
+const WARN_AT: f64 = 0.8;
+
+/// Limits per minute of the remote server.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy)]
+pub struct Quota {
+	pub requests: usize,
+	pub bytes: usize,
+}
+
+impl Quota {
+	/// Free tier of api.languagetool.org.
+	pub const FREE: Self = Self { requests: 20, bytes: 75_000 };
+}
+
+/// Error of a request beyond the quota, with the time at which it is within the quota again.
+#[derive(Debug, Clone, Copy)]
+pub struct QuotaReached(pub Instant);
+
+impl fmt::Display for QuotaReached {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "LanguageTool quota reached")
+	}
+}
+
+impl std::error::Error for QuotaReached {}
+
+/// Usage since the server started, nothing is stored between sessions.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy)]
+pub struct Statistics {
+	pub requests_today: usize,
+	pub bytes_today: usize,
+	pub requests_last_minute: usize,
+	pub bytes_last_minute: usize,
+	pub quota: Option<Quota>,
+}
+
+/// Requests sent to LanguageTool.
+#[derive(Debug, Default)]
+pub struct Usage {
+	minute: VecDeque<(Instant, usize)>,
+	day: u64,
+	requests_today: usize,
+	bytes_today: usize,
+	warned: bool,
+}
+
+impl Usage {
+	pub fn record(&mut self, bytes: usize) {
+		let day = today();
+		if day != self.day {
+			self.day = day;
+			self.requests_today = 0;
+			self.bytes_today = 0;
+		}
+		self.requests_today += 1;
+		self.bytes_today += bytes;
+		self.minute.push_back((Instant::now(), bytes));
+	}
+
+	pub fn statistics(&mut self, quota: Option<Quota>) -> Statistics {
+		self.prune();
+		if self.day != today() {
+			self.requests_today = 0;
+			self.bytes_today = 0;
+		}
+		Statistics {
+			requests_today: self.requests_today,
+			bytes_today: self.bytes_today,
+			requests_last_minute: self.minute.len(),
+			bytes_last_minute: self.minute.iter().map(|(_, bytes)| bytes).sum(),
+			quota,
+		}
+	}
+
+	/// Time at which another request is within the quota, `None` if it is already.
+	pub fn wait(&mut self, quota: Quota) -> Option<Instant> {
+		let statistics = self.statistics(Some(quota));
+		if statistics.requests_last_minute < quota.requests
+			&& statistics.bytes_last_minute < quota.bytes
+		{
+			return None;
+		}
+		self.minute.front().map(|(time, _)| *time + MINUTE)
+	}
+
+	/// Message when the usage reaches the warning threshold, once until it drops below it again.
+	pub fn warning(&mut self, quota: Quota) -> Option<String> {
+		let statistics = self.statistics(Some(quota));
+		let near = statistics.requests_last_minute as f64 >= quota.requests as f64 * WARN_AT
+			|| statistics.bytes_last_minute as f64 >= quota.bytes as f64 * WARN_AT;
+		if near.not() {
+			self.warned = false;
+			return None;
+		}
+		if std::mem::replace(&mut self.warned, true) {
+			return None;
+		}
+		Some(format!(
+			"LanguageTool quota: {}/{} requests and {}/{} KB in the last minute, checks are delayed at the limit.",
+			statistics.requests_last_minute,
+			quota.requests,
+			statistics.bytes_last_minute / 1000,
+			quota.bytes / 1000,
+		))
+	}
+
+	fn prune(&mut self) {
+		while self
+			.minute
+			.front()
+			.is_some_and(|(time, _)| time.elapsed() >= MINUTE)
+		{
+			self.minute.pop_front();
+		}
+	}
+}
+
+/// Days since the unix epoch.
+fn today() -> u64 {
+	let now = SystemTime::now()
+		.duration_since(SystemTime::UNIX_EPOCH)
+		.unwrap_or_default();
+	now.as_secs() / (24 * 60 * 60)
+}
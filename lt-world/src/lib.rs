@@ -10,6 +10,7 @@ use std::{
 use chrono::{DateTime, Datelike, FixedOffset, Local, Utc};
 use comemo::Prehashed;
 use fonts::FontManager;
+use ignore::{gitignore::GitignoreBuilder, Match, WalkBuilder};
 use typst::{
 	diag::{FileError, FileResult, SourceResult},
 	eval::Tracer,
@@ -20,6 +21,9 @@ use typst::{
 	Library, World,
 };
 
+/// Ignore file for paths which should not be checked, in addition to `.gitignore`.
+const LT_IGNORE: &str = ".ltignore";
+
 #[derive(Debug)]
 pub struct LtWorld {
 	library: Prehashed<Library>,
@@ -54,8 +58,60 @@ impl LtWorld {
 		&self.root
 	}
 
+	/// All Typst files of the project, see [`LtWorld::files`].
+	pub fn project_files(&self) -> Vec<PathBuf> {
+		self.files(&self.root)
+	}
+
+	/// All Typst files below the directory. Hidden files and files excluded by `.gitignore` or
+	/// `.ltignore` are skipped.
+	pub fn files(&self, dir: &Path) -> Vec<PathBuf> {
+		let mut files = WalkBuilder::new(dir)
+			.require_git(false)
+			.add_custom_ignore_filename(LT_IGNORE)
+			.build()
+			.flatten()
+			.filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+			.map(|entry| entry.into_path())
+			.filter(|path| path.extension().is_some_and(|ext| ext == "typ"))
+			.collect::<Vec<_>>();
+		files.sort();
+		files
+	}
+
+	/// Whether the path is excluded by a `.gitignore` or `.ltignore` between the root and the path.
+	pub fn is_ignored(&self, path: &Path) -> bool {
+		let Ok(path) = path.canonicalize() else {
+			return false;
+		};
+		let Ok(relative) = path.strip_prefix(&self.root) else {
+			return false;
+		};
+		let mut dirs = vec![self.root.clone()];
+		for component in relative.parent().into_iter().flat_map(Path::components) {
+			dirs.push(dirs[dirs.len() - 1].join(component));
+		}
+		// Ignore files in deeper directories take precedence.
+		for dir in dirs.iter().rev() {
+			let mut builder = GitignoreBuilder::new(dir);
+			for name in [".gitignore", LT_IGNORE] {
+				// Missing files are expected.
+				let _ = builder.add(dir.join(name));
+			}
+			let Ok(ignore) = builder.build() else {
+				continue;
+			};
+			match ignore.matched_path_or_any_parents(&path, path.is_dir()) {
+				Match::Ignore(_) => return true,
+				Match::Whitelist(_) => return false,
+				Match::None => {},
+			}
+		}
+		false
+	}
+
 	pub fn file_id(&self, path: &Path) -> Option<FileId> {
-		let path = path.canonicalize().unwrap();
+		let path = path.canonicalize().ok()?;
 		let path = path.strip_prefix(&self.root).ok()?;
 		let id = FileId::new(None, VirtualPath::new(path));
 		Some(id)
@@ -8,7 +8,7 @@ use jni::{
 	InitArgsBuilder, JNIEnv, JavaVM,
 };
 
-use crate::{LanguageToolBackend, Suggestion};
+use crate::{LanguageToolBackend, Severity, Suggestion};
 
 #[derive(Debug)]
 pub struct LanguageToolJNI {
@@ -117,6 +117,26 @@ impl LanguageToolJNI {
 				.call_method(&rule, "getDescription", "()Ljava/lang/String;", &[])?
 				.l()?;
 			let rule_description = env.get_string(&rule_description.into())?.into();
+			let category = env
+				.call_method(
+					&rule,
+					"getCategory",
+					"()Lorg/languagetool/rules/Category;",
+					&[],
+				)?
+				.l()?;
+			let category = env
+				.call_method(
+					&category,
+					"getId",
+					"()Lorg/languagetool/rules/CategoryId;",
+					&[],
+				)?
+				.l()?;
+			let category = env
+				.call_method(&category, "toString", "()Ljava/lang/String;", &[])?
+				.l()?;
+			let category = env.get_string(&category.into())?.into();
 
 			let suggestion = Suggestion {
 				start: start as usize,
@@ -125,6 +145,8 @@ impl LanguageToolJNI {
 				message,
 				rule_id,
 				rule_description,
+				category,
+				severity: Severity::Information,
 			};
 			suggestions.push(suggestion);
 		}
@@ -203,4 +225,33 @@ impl LanguageToolBackend for LanguageToolJNI {
 		)?;
 		Ok(())
 	}
+
+	async fn supported_languages(&mut self) -> anyhow::Result<Vec<String>> {
+		let mut guard = self.jvm.attach_current_thread()?;
+		let languages = guard
+			.call_static_method(
+				"org/languagetool/Languages",
+				"get",
+				"()Ljava/util/List;",
+				&[],
+			)?
+			.l()?;
+		let list = guard.get_list(&languages)?;
+		let mut codes = Vec::new();
+		for i in 0..list.size(&mut guard)? {
+			let Some(language) = list.get(&mut guard, i)? else {
+				continue;
+			};
+			let code = guard
+				.call_method(
+					&language,
+					"getShortCodeWithCountryAndVariant",
+					"()Ljava/lang/String;",
+					&[],
+				)?
+				.l()?;
+			codes.push(guard.get_string(&code.into())?.into());
+		}
+		Ok(codes)
+	}
 }
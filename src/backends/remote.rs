@@ -2,7 +2,7 @@ use std::collections::{HashMap, HashSet};
 
 use languagetool_rust::{check::Match, CheckRequest, ServerClient};
 
-use crate::{LanguageToolBackend, Suggestion};
+use crate::{LanguageToolBackend, Severity, Suggestion};
 
 #[derive(Debug)]
 pub struct LanguageToolRemote {
@@ -63,13 +63,20 @@ impl LanguageToolBackend for LanguageToolRemote {
 				message: m.message,
 				rule_description: m.rule.description,
 				rule_id: m.rule.id,
+				category: m.rule.category.id,
 				replacements: m.replacements.into_iter().map(|x| x.value).collect(),
+				severity: Severity::Information,
 			};
 			suggestions.push(suggestion);
 		}
 
 		Ok(suggestions)
 	}
+
+	async fn supported_languages(&mut self) -> anyhow::Result<Vec<String>> {
+		let languages = self.server_client.languages().await?;
+		Ok(languages.into_iter().map(|x| x.long_code).collect())
+	}
 }
 
 fn filter_match(m: &Match, allowed: &HashSet<String>) -> bool {
@@ -1,13 +1,23 @@
-use std::ops::Range;
+use std::{
+	collections::HashSet,
+	ops::{Not, Range},
+};
 
 use typst::{
 	layout::{Abs, Em, Point},
 	model::Document,
-	syntax::{FileId, Source, Span, SyntaxKind},
+	syntax::{
+		ast::{self, AstNode},
+		FileId, LinkedNode, Source, Span, SyntaxKind, SyntaxNode,
+	},
 	text::{Lang, TextItem},
 };
 
-use crate::Suggestion;
+use crate::{
+	fix::Piece,
+	profile::{self, Profile},
+	Suggestion,
+};
 
 #[derive(Debug)]
 pub struct Mapping {
@@ -17,8 +27,28 @@ pub struct Mapping {
 
 impl Mapping {
 	pub fn location(&self, suggestion: &Suggestion, source: &Source) -> Vec<Range<usize>> {
-		let chars = &self.chars[suggestion.start..suggestion.end];
 		let mut locations = Vec::<Range<usize>>::new();
+		for piece in self.pieces(suggestion, source) {
+			let range = piece.range();
+			match locations.last_mut() {
+				Some(last_range)
+					if last_range.start <= range.start && range.start <= last_range.end =>
+				{
+					last_range.end = last_range.end.max(range.end)
+				},
+				_ => locations.push(range),
+			}
+		}
+		locations
+	}
+
+	/// Source ranges of the suggestion, glyphs for multiple characters (ligatures) are only
+	/// included once.
+	pub(crate) fn pieces(&self, suggestion: &Suggestion, source: &Source) -> Vec<Piece> {
+		let Some(chars) = self.chars.get(suggestion.start..suggestion.end) else {
+			return Vec::new();
+		};
+		let mut pieces = Vec::<Piece>::new();
 		for (span, range) in chars.iter().cloned() {
 			let Some(id) = span.id() else {
 				continue;
@@ -29,22 +59,70 @@ impl Mapping {
 			let Some(node) = source.find(span) else {
 				continue;
 			};
-			if node.kind() == SyntaxKind::Text {
-				let start = node.range().start;
-				let range = (start + range.start as usize)..(start + range.end as usize);
-				match locations.last_mut() {
-					Some(last_range) if last_range.end == range.start => last_range.end = range.end,
-					_ => locations.push(range),
-				}
-			} else {
-				let range = node.range();
-				match locations.last_mut() {
-					Some(last_range) if *last_range == range => {},
-					_ => locations.push(range),
-				}
+			let piece = match text_start(&node) {
+				Some(start) => {
+					Piece::Literal((start + range.start as usize)..(start + range.end as usize))
+				},
+				None => Piece::Node(node.range()),
+			};
+			if pieces.last() != Some(&piece) {
+				pieces.push(piece);
 			}
 		}
-		locations
+		pieces
+	}
+
+	/// Whether any character of the text originates from one of the byte ranges of the source.
+	pub fn intersects(&self, source: &Source, ranges: &[Range<usize>]) -> bool {
+		let mut previous = None;
+		for span in self.spans() {
+			if previous == Some(span) || span.id() != Some(source.id()) {
+				continue;
+			}
+			previous = Some(span);
+			let Some(node) = source.find(span) else {
+				continue;
+			};
+			let node = node.range();
+			if ranges
+				.iter()
+				.any(|range| range.start < node.end && node.start < range.end)
+			{
+				return true;
+			}
+		}
+		false
+	}
+
+	/// Source of the character at the position in UTF-16 code units, `None` for generated text.
+	pub(crate) fn origin(&self, idx: usize) -> Option<(Span, Range<u16>)> {
+		self.chars
+			.get(idx)
+			.filter(|(span, _)| span.is_detached().not())
+			.cloned()
+	}
+
+	/// Whether the characters at the positions in UTF-16 code units are written as markup text and
+	/// not in strings, raw text or math. Generated text and text of other files is accepted.
+	pub(crate) fn is_markup(&self, source: &Source, range: Range<usize>) -> bool {
+		let Some(chars) = self.chars.get(range) else {
+			return false;
+		};
+		chars.iter().all(|(span, _)| {
+			if span.id() != Some(source.id()) {
+				return true;
+			}
+			source.find(*span).is_none_or(|node| {
+				node.kind() == SyntaxKind::Text
+					&& std::iter::successors(node.parent(), |node| node.parent()).all(|node| {
+						matches!(node.kind(), SyntaxKind::Raw | SyntaxKind::Equation).not()
+					})
+			})
+		})
+	}
+
+	fn spans(&self) -> impl Iterator<Item = Span> + '_ {
+		self.chars.iter().map(|(span, _)| *span)
 	}
 
 	pub fn short_language(&self) -> &str {
@@ -79,10 +157,320 @@ impl Mapping {
 	}
 }
 
+#[cfg(test)]
+impl Mapping {
+	/// Mapping with one entry per UTF-16 code unit.
+	pub(crate) fn from_chars(chars: Vec<(Span, Range<u16>)>) -> Self {
+		Self { chars, language: Lang::ENGLISH }
+	}
+
+	/// Mapping for generated text without a source.
+	pub(crate) fn generated(text: &str) -> Self {
+		Self::from_chars(vec![(Span::detached(), 0..0); text.encode_utf16().count()])
+	}
+}
+
+/// Start of the text the span offsets refer to, strings are only supported without escapes.
+fn text_start(node: &LinkedNode) -> Option<usize> {
+	match node.kind() {
+		SyntaxKind::Text => Some(node.range().start),
+		SyntaxKind::Str => {
+			let value = node.cast::<ast::Str>()?.get();
+			(node.text().len() == value.len() + 2).then(|| node.range().start + 1)
+		},
+		_ => None,
+	}
+}
+
 const LINE_SPACING: Em = Em::new(0.65);
 
-pub fn document(doc: &Document, chunk_size: usize, file_id: FileId) -> Vec<(String, Mapping)> {
+/// Paragraphs of a converted text with their offset in UTF-16 code units.
+pub fn paragraphs(text: &str) -> Vec<(usize, &str)> {
+	let mut paragraphs = Vec::new();
+	let mut offset = 0;
+	for paragraph in text.split("\n\n") {
+		if paragraph.trim().is_empty().not() {
+			paragraphs.push((offset, paragraph));
+		}
+		offset += paragraph.encode_utf16().count() + 2;
+	}
+	paragraphs
+}
+
+/// Arguments of `set document(...)` which are not part of the layout.
+const DOCUMENT_FIELDS: &[&str] = &["title", "author"];
+
+/// Arguments of template functions applied with `show: ...`.
+const TEMPLATE_FIELDS: &[&str] = &[
+	"title",
+	"subtitle",
+	"author",
+	"authors",
+	"abstract",
+	"description",
+];
+
+/// String literals for the document metadata and template arguments, which are not already part of
+/// the paragraphs. Checked with the document language.
+pub fn metadata(source: &Source, paragraphs: &[(String, Mapping)]) -> Option<(String, Mapping)> {
+	let mut strings = Vec::new();
+	metadata_strings(source.root(), &mut strings);
+
+	let known = paragraphs
+		.iter()
+		.flat_map(|(_, mapping)| mapping.spans())
+		.collect::<HashSet<_>>();
+	let mut converter = Converter::new(usize::MAX, document_language(source, paragraphs));
+	for string in strings {
+		if known.contains(&string.span()) {
+			continue;
+		}
+		let value = string.get();
+		if value.trim().is_empty() {
+			continue;
+		}
+		if converter.text.is_empty().not() {
+			converter.insert_parbreak(&mut Vec::new());
+		}
+		converter.literal(&value, string.span());
+	}
+	if converter.text.is_empty() {
+		return None;
+	}
+	Some((converter.text, converter.mapping))
+}
+
+/// Language of the first `set text(lang: ...)` rule, else of the first paragraph.
+pub(crate) fn document_language(source: &Source, paragraphs: &[(String, Mapping)]) -> Lang {
+	fn set_lang(node: &SyntaxNode) -> Option<Lang> {
+		if let Some(rule) = node.cast::<ast::SetRule>() {
+			if rule.target().to_untyped().text() == "text" {
+				let lang = rule.args().items().find_map(|arg| match arg {
+					ast::Arg::Named(named) if named.name().as_str() == "lang" => match named.expr()
+					{
+						ast::Expr::Str(lang) => lang.get().parse().ok(),
+						_ => None,
+					},
+					_ => None,
+				});
+				if lang.is_some() {
+					return lang;
+				}
+			}
+		}
+		node.children().find_map(set_lang)
+	}
+	set_lang(source.root())
+		.or_else(|| paragraphs.first().map(|(_, mapping)| mapping.language))
+		.unwrap_or(Lang::ENGLISH)
+}
+
+fn metadata_strings<'a>(node: &'a SyntaxNode, strings: &mut Vec<ast::Str<'a>>) {
+	if let Some(rule) = node.cast::<ast::SetRule>() {
+		if rule.target().to_untyped().text() == "document" {
+			named_strings(rule.args(), DOCUMENT_FIELDS, strings);
+		}
+	}
+	if let Some(rule) = node.cast::<ast::ShowRule>() {
+		if rule.selector().is_none() {
+			template_strings(rule.transform().to_untyped(), strings);
+		}
+	}
+	for child in node.children() {
+		metadata_strings(child, strings);
+	}
+}
+
+fn template_strings<'a>(node: &'a SyntaxNode, strings: &mut Vec<ast::Str<'a>>) {
+	if let Some(call) = node.cast::<ast::FuncCall>() {
+		named_strings(call.args(), TEMPLATE_FIELDS, strings);
+	}
+	for child in node.children() {
+		template_strings(child, strings);
+	}
+}
+
+fn named_strings<'a>(args: ast::Args<'a>, fields: &[&str], strings: &mut Vec<ast::Str<'a>>) {
+	for arg in args.items() {
+		let ast::Arg::Named(named) = arg else {
+			continue;
+		};
+		if fields.contains(&named.name().as_str()).not() {
+			continue;
+		}
+		match named.expr() {
+			ast::Expr::Str(string) => strings.push(string),
+			ast::Expr::Array(array) => {
+				for item in array.items() {
+					if let ast::ArrayItem::Pos(ast::Expr::Str(string)) = item {
+						strings.push(string);
+					}
+				}
+			},
+			_ => {},
+		}
+	}
+}
+
+/// Arguments of speaker note functions, which are not part of the layout. Every call is checked
+/// separately with the language of the first `set text(lang: ...)` rule, else of the first
+/// paragraph.
+pub fn notes(
+	source: &Source,
+	paragraphs: &[(String, Mapping)],
+	profiles: &[&Profile],
+) -> Vec<(String, Mapping)> {
+	let names = profiles
+		.iter()
+		.flat_map(|profile| &profile.notes)
+		.collect::<Vec<_>>();
+	let mut calls = Vec::new();
+	if names.is_empty().not() {
+		note_calls(source.root(), &names, &mut calls);
+	}
+
+	let language = document_language(source, paragraphs);
+	let mut notes = Vec::new();
+	for call in calls {
+		let mut converter = Converter::new(usize::MAX, language);
+		for arg in call.args().items() {
+			match arg {
+				ast::Arg::Pos(ast::Expr::Str(string)) => {
+					converter.literal(&string.get(), string.span())
+				},
+				ast::Arg::Pos(ast::Expr::Content(block)) => {
+					converter.markup(block.body().to_untyped())
+				},
+				_ => {},
+			}
+		}
+		if converter.text.trim().is_empty().not() {
+			notes.push((converter.text, converter.mapping));
+		}
+	}
+	notes
+}
+
+fn note_calls<'a>(node: &'a SyntaxNode, names: &[&String], calls: &mut Vec<ast::FuncCall<'a>>) {
+	if let Some(call) = profile::is_call(node, names) {
+		calls.push(call);
+		return;
+	}
+	for child in node.children() {
+		note_calls(child, names, calls);
+	}
+}
+
+/// Remove the text laid out from the byte ranges of the source (ignored functions, speaker
+/// notes, ...), so it is not sent to LanguageTool.
+pub fn remove_ranges(
+	source: &Source,
+	paragraphs: &mut Vec<(String, Mapping)>,
+	ranges: &[Range<usize>],
+) {
+	if ranges.is_empty() {
+		return;
+	}
+	let mut previous = None;
+	let mut inside = |span: Span| {
+		if let Some((_, inside)) = previous.filter(|(previous, _)| *previous == span) {
+			return inside;
+		}
+		let inside = span.id() == Some(source.id())
+			&& source.range(span).is_some_and(|node| {
+				ranges
+					.iter()
+					.any(|range| range.start <= node.start && node.end <= range.end)
+			});
+		previous = Some((span, inside));
+		inside
+	};
+	for (text, mapping) in paragraphs.iter_mut() {
+		let mut chars = mapping.chars.iter();
+		let mut kept_text = String::new();
+		let mut kept = Vec::new();
+		for c in text.chars() {
+			let units = chars.by_ref().take(c.len_utf16()).collect::<Vec<_>>();
+			if units.iter().any(|(span, _)| inside(*span)) {
+				continue;
+			}
+			kept_text.push(c);
+			kept.extend(units.into_iter().cloned());
+		}
+		*text = kept_text;
+		mapping.chars = kept;
+	}
+	paragraphs.retain(|(text, _)| text.trim().is_empty().not());
+}
+
+/// Remove pages whose file text is contained in the next page, like the overlays of a slide
+/// showing its content step by step.
+pub fn remove_overlays(paragraphs: &mut Vec<(String, Mapping)>) {
+	let contents = paragraphs
+		.iter()
+		.map(|(_, mapping)| {
+			mapping
+				.chars
+				.iter()
+				.filter(|(span, _)| span.is_detached().not())
+				.cloned()
+				.collect::<HashSet<_>>()
+		})
+		.collect::<Vec<_>>();
+	// Only the next page, so text repeated later in the document is still checked.
+	let overlay = contents
+		.iter()
+		.zip(contents.iter().skip(1).map(Some).chain([None]))
+		.map(|(content, next)| {
+			content.is_empty().not() && next.is_some_and(|next| content.is_subset(next))
+		})
+		.collect::<Vec<_>>();
+	let mut overlay = overlay.into_iter();
+	paragraphs.retain(|_| overlay.next() != Some(true));
+}
+
+/// Element of the file which is not part of the converted text.
+#[derive(Debug, Clone)]
+pub struct Skipped {
+	/// Kind of the element ("image", "shape", "text", ...).
+	pub kind: &'static str,
+	pub span: Span,
+}
+
+/// Shape functions, other shapes are backgrounds or strokes of checked elements (tables, ...).
+const DRAWINGS: [&str; 7] = [
+	"rect", "square", "circle", "ellipse", "line", "polygon", "path",
+];
+
+impl Skipped {
+	/// Whether the element is worth a hint, shapes only for drawings without content.
+	pub fn is_reported(&self, source: &Source) -> bool {
+		if self.kind != "shape" {
+			return true;
+		}
+		let names = DRAWINGS.map(String::from);
+		let names = names.iter().collect::<Vec<_>>();
+		let Some(node) = source.find(self.span) else {
+			return false;
+		};
+		let Some(call) = profile::is_call(node.get(), &names) else {
+			return false;
+		};
+		call.args().items().all(|arg| match arg {
+			ast::Arg::Pos(expr) => matches!(expr, ast::Expr::Content(_)).not(),
+			ast::Arg::Named(named) => named.name().as_str() != "body",
+			ast::Arg::Spread(_) => true,
+		})
+	}
+}
+
+pub fn document(
+	doc: &Document,
+	chunk_size: usize,
+	file_id: FileId,
+) -> (Vec<(String, Mapping)>, Vec<Skipped>) {
 	let mut res = Vec::new();
+	let mut skipped = Vec::new();
 
 	for page in &doc.pages {
 		let mut converter = Converter::new(chunk_size, Lang::ENGLISH);
@@ -90,8 +478,11 @@ pub fn document(doc: &Document, chunk_size: usize, file_id: FileId) -> Vec<(Stri
 		if converter.contains_file {
 			res.push((converter.text, converter.mapping));
 		}
+		skipped.extend(converter.skipped);
 	}
-	res
+	let mut seen = HashSet::new();
+	skipped.retain(|element| seen.insert(element.span));
+	(res, skipped)
 }
 
 struct Converter {
@@ -102,6 +493,7 @@ struct Converter {
 	span: (Span, u16),
 	chunk_size: usize,
 	contains_file: bool,
+	skipped: Vec<Skipped>,
 }
 
 impl Converter {
@@ -114,6 +506,58 @@ impl Converter {
 			span: (Span::detached(), 0),
 			contains_file: false,
 			chunk_size,
+			skipped: Vec::new(),
+		}
+	}
+
+	/// Text copied from the node with the span.
+	fn literal(&mut self, text: &str, span: Span) {
+		self.text += text;
+		for (offset, c) in text.char_indices() {
+			let range = offset as u16..(offset + c.len_utf8()) as u16;
+			for _ in 0..c.len_utf16() {
+				self.mapping.chars.push((span, range.clone()));
+			}
+		}
+	}
+
+	/// Text generated by the node with the span.
+	fn generated(&mut self, text: &str, span: Span) {
+		self.text += text;
+		for _ in text.encode_utf16() {
+			self.mapping.chars.push((span, 0..0));
+		}
+	}
+
+	/// Text of markup, which is not laid out. Code and math are skipped.
+	fn markup(&mut self, node: &SyntaxNode) {
+		match node.kind() {
+			SyntaxKind::Text => self.literal(node.text(), node.span()),
+			SyntaxKind::Space | SyntaxKind::Linebreak => self.generated(" ", node.span()),
+			SyntaxKind::Parbreak => self.insert_parbreak(&mut Vec::new()),
+			SyntaxKind::SmartQuote => self.generated(node.text(), node.span()),
+			SyntaxKind::Shorthand => {
+				if let Some(shorthand) = node.cast::<ast::Shorthand>() {
+					self.generated(&shorthand.get().to_string(), node.span());
+				}
+			},
+			SyntaxKind::Escape => {
+				if let Some(escape) = node.cast::<ast::Escape>() {
+					self.generated(&escape.get().to_string(), node.span());
+				}
+			},
+			SyntaxKind::Markup
+			| SyntaxKind::Strong
+			| SyntaxKind::Emph
+			| SyntaxKind::Heading
+			| SyntaxKind::ListItem
+			| SyntaxKind::EnumItem
+			| SyntaxKind::TermItem => {
+				for child in node.children() {
+					self.markup(child);
+				}
+			},
+			_ => {},
 		}
 	}
 
@@ -135,7 +579,15 @@ impl Converter {
 			);
 			res.push((text, mapping));
 		}
+		let skipped = std::mem::take(&mut self.skipped);
 		*self = Converter::new(self.chunk_size, language);
+		self.skipped = skipped;
+	}
+
+	fn skip(&mut self, kind: &'static str, span: Span, file_id: FileId) {
+		if span.id() == Some(file_id) {
+			self.skipped.push(Skipped { kind, span });
+		}
 	}
 
 	fn insert_parbreak(&mut self, res: &mut Vec<(String, Mapping)>) {
@@ -189,6 +641,11 @@ impl Converter {
 		match item {
 			I::Group(g) => self.frame(&g.frame, pos, res, file_id),
 			I::Text(t) => {
+				let Some(chars) = glyph_chars(t) else {
+					let span = t.glyphs.first().map_or(Span::detached(), |g| g.span.0);
+					self.skip("text", span, file_id);
+					return;
+				};
 				if self.mapping.language != t.lang {
 					self.seperate(res);
 				}
@@ -199,27 +656,185 @@ impl Converter {
 				self.y = pos.y;
 				self.text += t.text.as_str();
 
-				let mut iter = t.text.encode_utf16();
-				for g in t.glyphs.iter().cloned() {
-					let Some(text) = t.text.get(g.range()) else {
-						continue;
-					};
-					for t in text.encode_utf16() {
-						assert_eq!(t, iter.next().unwrap());
-
-						let m = (g.span.0, g.span.1..(g.span.1 + g.range.len() as u16));
-						if let Some(id) = m.0.id() {
-							self.span = (m.0, m.1.end);
-							if id == file_id {
-								self.contains_file = true;
-							}
+				for m in chars {
+					if let Some(id) = m.0.id() {
+						self.span = (m.0, m.1.end);
+						if id == file_id {
+							self.contains_file = true;
 						}
-						self.mapping.chars.push(m);
 					}
+					self.mapping.chars.push(m);
 				}
-				assert_eq!(None, iter.next());
 			},
-			I::Meta(M::Link(..) | M::Elem(..) | M::Hide, _) | I::Shape(..) | I::Image(..) => {},
+			I::Image(_, _, span) => self.skip("image", *span, file_id),
+			I::Shape(_, span) => self.skip("shape", *span, file_id),
+			// Links and elements are only positions of the laid out content, nothing is shown.
+			I::Meta(M::Link(..) | M::Elem(..) | M::Hide, _) => {},
+		}
+	}
+}
+
+/// Span for every UTF-16 code unit of the text, `None` if the glyphs don't cover the text.
+fn glyph_chars(text: &TextItem) -> Option<Vec<(Span, Range<u16>)>> {
+	let mut iter = text.text.encode_utf16();
+	let mut chars = Vec::new();
+	for g in text.glyphs.iter() {
+		let Some(glyph_text) = text.text.get(g.range()) else {
+			continue;
+		};
+		for unit in glyph_text.encode_utf16() {
+			if iter.next() != Some(unit) {
+				return None;
+			}
+			chars.push((g.span.0, g.span.1..(g.span.1 + g.range.len() as u16)));
 		}
 	}
+	iter.next().is_none().then_some(chars)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Mapping with every character of the leaves containing the texts.
+	fn leaves(source: &Source, texts: &[&str]) -> (String, Mapping) {
+		fn visit(node: &LinkedNode, found: &mut Vec<(String, Span)>) {
+			if node.children().len() == 0 {
+				found.push((node.text().to_string(), node.span()));
+			}
+			for child in node.children() {
+				visit(&child, found);
+			}
+		}
+		let mut found = Vec::new();
+		visit(&LinkedNode::new(source.root()), &mut found);
+		let mut text = String::new();
+		let mut chars = Vec::new();
+		for wanted in texts {
+			let (leaf, span) = found.iter().find(|(leaf, _)| leaf == wanted).unwrap();
+			text += leaf;
+			chars.extend(std::iter::repeat_n(
+				(*span, 0..1),
+				leaf.encode_utf16().count(),
+			));
+		}
+		(text, Mapping::from_chars(chars))
+	}
+
+	#[test]
+	fn markup_text() {
+		let source = Source::detached("a `b` #\"c\" $ d $ *e*");
+		for (text, markup) in [
+			("a", true),
+			("b", false),
+			("\"c\"", false),
+			("d", false),
+			("e", true),
+		] {
+			let (_, mapping) = leaves(&source, &[text]);
+			assert_eq!(mapping.is_markup(&source, 0..1), markup, "{}", text);
+		}
+		let (_, mapping) = leaves(&source, &["a"]);
+		assert!(mapping.is_markup(&source, 0..5).not());
+	}
+
+	#[test]
+	fn language_of_set_rule() {
+		let lang = |text: &str| document_language(&Source::detached(text), &[]);
+		assert_eq!(
+			lang("#set text(size: 12pt)\n#set text(lang: \"de\")"),
+			Lang::GERMAN
+		);
+		assert_eq!(lang("#[#set text(lang: \"fr\")]"), Lang::FRENCH);
+		assert_eq!(lang("#set text(lang: \"invalid\")\nText"), Lang::ENGLISH);
+	}
+
+	#[test]
+	fn reported_shapes() {
+		let source = Source::detached(
+			"#rect(width: 1cm)
+#rect[a]
+#table[b]
+#line(length: 1cm)",
+		);
+		let calls = source
+			.root()
+			.children()
+			.filter(|node| node.kind() == SyntaxKind::FuncCall)
+			.map(|node| node.span())
+			.collect::<Vec<_>>();
+		let reported = |kind, span| Skipped { kind, span }.is_reported(&source);
+		assert_eq!(
+			calls
+				.iter()
+				.map(|&span| reported("shape", span))
+				.collect::<Vec<_>>(),
+			[true, false, false, true]
+		);
+		assert!(reported("image", calls[1]));
+		assert!(reported("shape", Span::detached()).not());
+	}
+
+	#[test]
+	fn pieces_out_of_range() {
+		let source = Source::detached("a");
+		let (_, mapping) = leaves(&source, &["a"]);
+		let suggestion = Suggestion {
+			start: 0,
+			end: 2,
+			message: String::new(),
+			replacements: Vec::new(),
+			rule_description: String::new(),
+			rule_id: String::new(),
+			category: String::new(),
+			severity: crate::Severity::Hint,
+		};
+		assert!(mapping.pieces(&suggestion, &source).is_empty());
+	}
+
+	#[test]
+	fn paragraph_offsets() {
+		let text = "\n\nFirst.\n\nSecond 😀.\n\n\n\nThird";
+		assert_eq!(
+			paragraphs(text),
+			[(2, "First."), (10, "Second 😀."), (24, "Third")]
+		);
+		assert_eq!(paragraphs(" \n\n"), []);
+	}
+
+	#[test]
+	fn overlays_are_removed() {
+		let source = Source::detached("a *b* _c_");
+		let mut pages = vec![
+			leaves(&source, &["a"]),
+			leaves(&source, &["a", "b"]),
+			leaves(&source, &["c"]),
+			leaves(&source, &["a"]),
+			("1".into(), Mapping::generated("1")),
+			("2".into(), Mapping::generated("2")),
+		];
+		remove_overlays(&mut pages);
+		let texts = pages
+			.iter()
+			.map(|(text, _)| text.as_str())
+			.collect::<Vec<_>>();
+		// Repeated later and generated pages are kept.
+		assert_eq!(texts, ["ab", "c", "a", "1", "2"]);
+	}
+
+	#[test]
+	fn ranges_are_removed() {
+		let source = Source::detached("a #f[b] c #f[d]");
+		let call = |text: &str| {
+			let start = source.text().find(text).unwrap();
+			start..start + text.len()
+		};
+		let mut paragraphs = vec![leaves(&source, &["a", "b", "c"]), leaves(&source, &["d"])];
+		remove_ranges(&source, &mut paragraphs, &[call("f[b]")]);
+		assert_eq!(paragraphs.len(), 2);
+		assert_eq!(paragraphs[0].0, "ac");
+		assert_eq!(paragraphs[0].1.chars.len(), 2);
+		remove_ranges(&source, &mut paragraphs, &[call("f[d]")]);
+		assert_eq!(paragraphs.len(), 1);
+	}
 }
@@ -0,0 +1,296 @@
+use std::ops::{Not, Range};
+
+use serde::{Deserialize, Serialize};
+
+/// Replacement of a byte range in the source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edit {
+	pub range: Range<usize>,
+	pub text: String,
+}
+
+/// Origin of a part of the checked text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Piece {
+	/// Text copied verbatim from the source range.
+	Literal(Range<usize>),
+	/// Text generated by the node at the source range (spaces, shorthands, escapes, ...).
+	Node(Range<usize>),
+}
+
+impl Piece {
+	pub(crate) fn range(&self) -> Range<usize> {
+		match self {
+			Piece::Literal(range) | Piece::Node(range) => range.clone(),
+		}
+	}
+}
+
+enum Cell {
+	Char(Range<usize>, char),
+	/// Node with a flag if its source is whitespace.
+	Node(Range<usize>, bool),
+}
+
+impl Cell {
+	fn range(&self) -> Range<usize> {
+		match self {
+			Cell::Char(range, _) | Cell::Node(range, _) => range.clone(),
+		}
+	}
+
+	fn char(&self) -> Option<char> {
+		match self {
+			Cell::Char(_, c) => Some(*c),
+			Cell::Node(..) => None,
+		}
+	}
+
+	fn is_space(&self) -> bool {
+		match self {
+			Cell::Char(_, c) => c.is_whitespace(),
+			Cell::Node(_, space) => *space,
+		}
+	}
+}
+
+/// Edits replacing the pieces with the replacement.
+///
+/// Words are replaced separately if the replacement has the same number of words. Only the
+/// differing middle part of a word is replaced, so markup around the common prefix and suffix
+/// stays in place. The replacement is split across the markup boundaries inside the middle part
+/// and follows the capitalization of the original text.
+pub(crate) fn edits(source: &str, pieces: &[Piece], replacement: &str) -> Vec<Edit> {
+	let mut cells = Vec::new();
+	for piece in pieces {
+		match piece {
+			Piece::Literal(range) => {
+				let Some(text) = source.get(range.clone()) else {
+					continue;
+				};
+				for (offset, c) in text.char_indices() {
+					let start = range.start + offset;
+					cells.push(Cell::Char(start..start + c.len_utf8(), c));
+				}
+			},
+			Piece::Node(range) => {
+				let space = source
+					.get(range.clone())
+					.is_some_and(|text| text.trim().is_empty());
+				cells.push(Cell::Node(range.clone(), space));
+			},
+		}
+	}
+	if cells.is_empty() {
+		return Vec::new();
+	}
+
+	let original = cells.iter().filter_map(Cell::char).collect::<String>();
+	let replacement = preserve_case(&original, replacement)
+		.chars()
+		.collect::<Vec<_>>();
+
+	let mut edits = Vec::new();
+	match (
+		words(&cells, Cell::is_space),
+		words(&replacement, |c| c.is_whitespace()),
+	) {
+		(Some(original), Some(replacement)) if original.len() == replacement.len() => {
+			for (cells, replacement) in original.into_iter().zip(replacement) {
+				diff(source, cells, replacement, &mut edits);
+			}
+		},
+		_ => diff(source, &cells, &replacement, &mut edits),
+	}
+	edits
+}
+
+/// Items separated by whitespace, `None` if there is leading or trailing whitespace.
+fn words<T>(items: &[T], space: impl Fn(&T) -> bool) -> Option<Vec<&[T]>> {
+	if items.first().is_none_or(&space) || items.last().is_none_or(&space) {
+		return None;
+	}
+	Some(
+		items
+			.split(space)
+			.filter(|word| word.is_empty().not())
+			.collect(),
+	)
+}
+
+fn diff(source: &str, cells: &[Cell], replacement: &[char], edits: &mut Vec<Edit>) {
+	let mut prefix = 0;
+	while prefix < cells.len()
+		&& prefix < replacement.len()
+		&& cells[prefix].char() == Some(replacement[prefix])
+	{
+		prefix += 1;
+	}
+	let mut suffix = 0;
+	while suffix < cells.len() - prefix
+		&& suffix < replacement.len() - prefix
+		&& cells[cells.len() - 1 - suffix].char()
+			== Some(replacement[replacement.len() - 1 - suffix])
+	{
+		suffix += 1;
+	}
+	let middle = &cells[prefix..cells.len() - suffix];
+	let mut text = &replacement[prefix..replacement.len() - suffix];
+
+	if middle.is_empty() {
+		if text.is_empty().not() {
+			let position = match prefix {
+				0 => cells[0].range().start,
+				_ => cells[prefix - 1].range().end,
+			};
+			edits.push(Edit {
+				range: position..position,
+				text: text.iter().collect(),
+			});
+		}
+		return;
+	}
+
+	// Consecutive characters, separated by markup or generated text.
+	let mut runs = Vec::<(Range<usize>, usize)>::new();
+	let mut generated = false;
+	for cell in middle {
+		let range = cell.range();
+		match (cell, runs.last_mut()) {
+			(Cell::Char(..), Some((last, count))) if last.end == range.start && *count > 0 => {
+				last.end = range.end;
+				*count += 1;
+			},
+			(Cell::Char(..), _) => runs.push((range, 1)),
+			(Cell::Node(..), _) => {
+				generated = true;
+				runs.push((range, 0));
+			},
+		}
+	}
+
+	let last = runs.len() - 1;
+	for (idx, (range, count)) in runs.into_iter().enumerate() {
+		// Generated text can't be split, the first run gets the complete replacement then.
+		let take = match generated || idx == last {
+			true => text.len(),
+			false => count.min(text.len()),
+		};
+		let (head, rest) = text.split_at(take);
+		text = rest;
+		let new = head.iter().collect::<String>();
+		if source.get(range.clone()) != Some(new.as_str()) {
+			edits.push(Edit { range, text: new });
+		}
+	}
+}
+
+fn preserve_case(original: &str, replacement: &str) -> String {
+	// Fixes of the capitalization itself.
+	if original.to_lowercase() == replacement.to_lowercase() {
+		return replacement.into();
+	}
+	let mut letters = original.chars().filter(|c| c.is_alphabetic());
+	let upper = letters.clone().nth(1).is_some() && letters.all(char::is_uppercase);
+	if upper && replacement.chars().all(|c| c.is_uppercase().not()) {
+		return replacement.to_uppercase();
+	}
+	let mut chars = replacement.chars();
+	match (original.chars().next(), chars.next()) {
+		(Some(o), Some(r)) if o.is_uppercase() && r.is_lowercase() => {
+			r.to_uppercase().chain(chars).collect()
+		},
+		_ => replacement.into(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn edit(range: Range<usize>, text: &str) -> Edit {
+		Edit { range, text: text.into() }
+	}
+
+	#[test]
+	fn common_parts_are_kept() {
+		let pieces = [Piece::Literal(2..5)];
+		assert_eq!(edits("a teh b", &pieces, "the"), [edit(3..5, "he")]);
+		assert_eq!(edits("a teh b", &pieces, "teh"), []);
+	}
+
+	#[test]
+	fn capitalization_follows_the_original() {
+		assert_eq!(
+			edits("Teh", &[Piece::Literal(0..3)], "the"),
+			[edit(1..3, "he")]
+		);
+		assert_eq!(
+			edits("TEH", &[Piece::Literal(0..3)], "the"),
+			[edit(1..3, "HE")]
+		);
+	}
+
+	#[test]
+	fn case_only_fixes() {
+		assert_eq!(
+			edits("Heute", &[Piece::Literal(0..5)], "heute"),
+			[edit(0..1, "h")]
+		);
+		assert_eq!(
+			edits("THE", &[Piece::Literal(0..3)], "the"),
+			[edit(0..3, "the")]
+		);
+		assert_eq!(
+			edits("berlin", &[Piece::Literal(0..6)], "Berlin"),
+			[edit(0..1, "B")]
+		);
+	}
+
+	#[test]
+	fn markup_boundaries_stay_in_place() {
+		// Word split by strong emphasis: `rec*ieve*`.
+		let pieces = [Piece::Literal(0..3), Piece::Literal(4..8)];
+		assert_eq!(edits("rec*ieve*", &pieces, "receive"), [edit(4..6, "ei")]);
+		let pieces = [
+			Piece::Literal(0..1),
+			Piece::Literal(2..3),
+			Piece::Literal(4..5),
+		];
+		assert_eq!(
+			edits("a*b*c", &pieces, "xyz"),
+			[edit(0..1, "x"), edit(2..3, "y"), edit(4..5, "z")]
+		);
+	}
+
+	#[test]
+	fn words_are_replaced_separately() {
+		// `teh *cta*` with the space between the words.
+		let source = "teh *cta*";
+		let pieces = [
+			Piece::Literal(0..3),
+			Piece::Node(3..4),
+			Piece::Literal(5..8),
+		];
+		assert_eq!(
+			edits(source, &pieces, "the cat"),
+			[edit(1..3, "he"), edit(6..8, "at")]
+		);
+		// Different number of words, the space can't be split.
+		assert_eq!(
+			edits(source, &pieces, "thecat"),
+			[edit(1..3, "hecat"), edit(3..4, ""), edit(5..8, "")]
+		);
+	}
+
+	#[test]
+	fn generated_text_gets_the_replacement() {
+		// Shorthand `--` for an en dash.
+		let pieces = [
+			Piece::Literal(0..1),
+			Piece::Node(1..3),
+			Piece::Literal(3..4),
+		];
+		assert_eq!(edits("a--b", &pieces, "a-b"), [edit(1..3, "-")]);
+	}
+}
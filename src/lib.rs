@@ -1,10 +1,20 @@
 mod backends;
 pub mod convert;
+pub mod fix;
+pub mod profile;
+pub mod report;
+pub mod rules;
+pub mod scope;
+#[cfg(feature = "spellcheck")]
+pub mod spell;
 
-use std::ops::{Not, Range};
+use std::{
+	collections::HashSet,
+	ops::{Not, Range},
+};
 
 pub use backends::*;
-use convert::Mapping;
+use convert::{Mapping, Skipped};
 use typst::{
 	syntax::{FileId, Source},
 	World,
@@ -15,6 +25,8 @@ pub trait LanguageToolBackend {
 	async fn allow_words(&mut self, lang: String, words: &[String]) -> anyhow::Result<()>;
 	async fn disable_checks(&mut self, lang: String, checks: &[String]) -> anyhow::Result<()>;
 	async fn check_text(&mut self, lang: String, text: &str) -> anyhow::Result<Vec<Suggestion>>;
+	/// Language codes with region and variant ("en-US", ...).
+	async fn supported_languages(&mut self) -> anyhow::Result<Vec<String>>;
 }
 
 #[derive(Debug)]
@@ -86,37 +98,108 @@ impl LanguageToolBackend for LanguageTool {
 			Self::Remote(lt) => lt.check_text(lang, text).await,
 		}
 	}
+	async fn supported_languages(&mut self) -> anyhow::Result<Vec<String>> {
+		match self {
+			#[cfg(any(feature = "bundle-jar", feature = "extern-jar"))]
+			Self::JNI(lt) => lt.supported_languages().await,
+			#[cfg(feature = "remote-server")]
+			Self::Remote(lt) => lt.supported_languages().await,
+		}
+	}
+}
+
+/// Supported language with the same language code, preferring the same region.
+pub fn closest_language<'a>(lang: &str, supported: &'a [String]) -> Option<&'a str> {
+	let mut parts = lang.split('-');
+	let short = parts.next().unwrap_or(lang);
+	let region = parts.next();
+	let part = |code: &str, idx: usize| code.split('-').nth(idx).map(str::to_lowercase);
+	let candidates = supported
+		.iter()
+		.filter(|code| part(code, 0) == Some(short.to_lowercase()));
+	candidates
+		.clone()
+		.find(|code| region.is_some() && part(code, 1) == region.map(str::to_lowercase))
+		.or_else(|| candidates.min_by_key(|code| code.len()))
+		.map(String::as_str)
 }
 
 pub struct FileCollector {
 	source: Source,
 	diagnostics: Vec<Diagnostic>,
+	deduplicate: bool,
 }
 
 impl FileCollector {
 	pub fn new(file_id: FileId, world: &impl World) -> Self {
 		let source = world.source(file_id).unwrap();
-		Self { source, diagnostics: Vec::new() }
+		Self {
+			source,
+			diagnostics: Vec::new(),
+			deduplicate: false,
+		}
 	}
 
-	pub fn add(&mut self, suggestions: &[Suggestion], mapping: Mapping) {
+	pub fn add(&mut self, suggestions: &[Suggestion], mapping: &Mapping) {
 		let diagnostics = suggestions
 			.iter()
 			.map(|suggestion| {
 				let locations = mapping.location(suggestion, &self.source);
+				let pieces = mapping.pieces(suggestion, &self.source);
+				let fixes = suggestion
+					.replacements
+					.iter()
+					.map(|replacement| fix::edits(self.source.text(), &pieces, replacement))
+					.collect();
 				Diagnostic {
 					locations,
 					message: suggestion.message.clone(),
 					replacements: suggestion.replacements.clone(),
+					fixes,
 					rule_description: suggestion.rule_description.clone(),
 					rule_id: suggestion.rule_id.clone(),
+					category: suggestion.category.clone(),
+					severity: suggestion.severity,
 				}
 			})
 			.filter(|diagnostic| diagnostic.locations.is_empty().not());
 		self.diagnostics.extend(diagnostics)
 	}
 
-	pub fn finish(self) -> (Source, Vec<Diagnostic>) {
+	/// Hints for elements which are not checked.
+	pub fn add_skipped(&mut self, skipped: &[Skipped]) {
+		let diagnostics = skipped.iter().filter_map(|element| {
+			if element.is_reported(&self.source).not() {
+				return None;
+			}
+			let range = self.source.range(element.span)?;
+			Some(Diagnostic {
+				locations: vec![range],
+				message: format!("The {} is not checked.", element.kind),
+				replacements: Vec::new(),
+				fixes: Vec::new(),
+				rule_description: "Skipped element".into(),
+				rule_id: "SKIPPED_ELEMENT".into(),
+				category: "SKIPPED".into(),
+				severity: Severity::Hint,
+			})
+		});
+		self.diagnostics.extend(diagnostics)
+	}
+
+	/// Drop repeated diagnostics for the same location, text shown on several overlays of a slide
+	/// is checked for every overlay.
+	pub fn deduplicate(&mut self) {
+		self.deduplicate = true;
+	}
+
+	pub fn finish(mut self) -> (Source, Vec<Diagnostic>) {
+		if self.deduplicate {
+			let mut seen = HashSet::new();
+			self.diagnostics.retain(|diagnostic| {
+				seen.insert((diagnostic.locations.clone(), diagnostic.rule_id.clone()))
+			});
+		}
 		(self.source, self.diagnostics)
 	}
 }
@@ -126,8 +209,12 @@ pub struct Diagnostic {
 	pub locations: Vec<Range<usize>>,
 	pub message: String,
 	pub replacements: Vec<String>,
+	/// Source edits for every replacement, keeping markup and capitalization.
+	pub fixes: Vec<Vec<fix::Edit>>,
 	pub rule_description: String,
 	pub rule_id: String,
+	pub category: String,
+	pub severity: Severity,
 }
 
 #[derive(Debug, Clone)]
@@ -138,4 +225,166 @@ pub struct Suggestion {
 	pub replacements: Vec<String>,
 	pub rule_description: String,
 	pub rule_id: String,
+	pub category: String,
+	pub severity: Severity,
+}
+
+/// Categories of rules, whose findings are merged.
+const MERGED_CATEGORIES: [&str; 2] = ["TYPOGRAPHY", "PUNCTUATION"];
+
+/// Merge suggestions of the same whitespace or punctuation rule, which are only separated by
+/// whitespace. The merged suggestion keeps the first message and combines the preferred
+/// replacements.
+pub fn merge_adjacent(text: &str, suggestions: &mut Vec<Suggestion>) {
+	let text = text.encode_utf16().collect::<Vec<_>>();
+	suggestions.sort_by_key(|suggestion| suggestion.start);
+	let mut merged = Vec::<Suggestion>::with_capacity(suggestions.len());
+	for suggestion in suggestions.drain(..) {
+		let Some(last) = merged.last_mut() else {
+			merged.push(suggestion);
+			continue;
+		};
+		if last.rule_id != suggestion.rule_id
+			|| last.end > suggestion.start
+			|| is_mergeable(last).not()
+		{
+			merged.push(suggestion);
+			continue;
+		}
+		let gap = String::from_utf16_lossy(&text[last.end..suggestion.start]);
+		if gap.trim().is_empty().not() {
+			merged.push(suggestion);
+			continue;
+		}
+		last.replacements = match (last.replacements.first(), suggestion.replacements.first()) {
+			(Some(first), Some(second)) => vec![format!("{}{}{}", first, gap, second)],
+			_ => Vec::new(),
+		};
+		last.end = suggestion.end;
+	}
+	*suggestions = merged;
+}
+
+fn is_mergeable(suggestion: &Suggestion) -> bool {
+	MERGED_CATEGORIES.contains(&suggestion.category.as_str())
+		|| suggestion.rule_id.contains("WHITESPACE")
+		|| suggestion.rule_id.contains("PUNCTUATION")
+}
+
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	Hash,
+	serde::Serialize,
+	serde::Deserialize
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+	Error,
+	Warning,
+	Information,
+	Hint,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn suggestion(
+		start: usize,
+		end: usize,
+		rule_id: &str,
+		category: &str,
+		fix: &str,
+	) -> Suggestion {
+		Suggestion {
+			start,
+			end,
+			message: String::new(),
+			replacements: vec![fix.into()],
+			rule_description: String::new(),
+			rule_id: rule_id.into(),
+			category: category.into(),
+			severity: Severity::Warning,
+		}
+	}
+
+	fn merged(text: &str, mut suggestions: Vec<Suggestion>) -> Vec<(usize, usize, Vec<String>)> {
+		merge_adjacent(text, &mut suggestions);
+		suggestions
+			.into_iter()
+			.map(|x| (x.start, x.end, x.replacements))
+			.collect()
+	}
+
+	#[test]
+	fn whitespace_findings_are_merged() {
+		let found = merged(
+			"a ,  , b",
+			vec![
+				suggestion(4, 6, "COMMA_PARENTHESIS_WHITESPACE", "TYPOGRAPHY", ","),
+				suggestion(1, 3, "COMMA_PARENTHESIS_WHITESPACE", "TYPOGRAPHY", ","),
+			],
+		);
+		assert_eq!(found, [(1, 6, vec![", ,".into()])]);
+	}
+
+	#[test]
+	fn other_findings_are_kept() {
+		let found = merged(
+			"teh teh",
+			vec![
+				suggestion(0, 3, "MORFOLOGIK_RULE_EN_US", "TYPOS", "the"),
+				suggestion(4, 7, "MORFOLOGIK_RULE_EN_US", "TYPOS", "the"),
+			],
+		);
+		assert_eq!(found.len(), 2);
+		// Different rules or text in between.
+		let found = merged(
+			"a , , b ,",
+			vec![
+				suggestion(1, 3, "WHITESPACE_RULE", "TYPOGRAPHY", ","),
+				suggestion(3, 5, "COMMA_PARENTHESIS_WHITESPACE", "TYPOGRAPHY", ","),
+				suggestion(7, 9, "COMMA_PARENTHESIS_WHITESPACE", "TYPOGRAPHY", ","),
+			],
+		);
+		assert_eq!(found.len(), 3);
+	}
+
+	#[test]
+	fn positions_are_utf16() {
+		let text = "😀 !  !";
+		let found = merged(
+			text,
+			vec![
+				suggestion(2, 4, "DOUBLE_PUNCTUATION", "PUNCTUATION", "!"),
+				suggestion(5, 7, "DOUBLE_PUNCTUATION", "PUNCTUATION", "!"),
+			],
+		);
+		assert_eq!(found, [(2, 7, vec!["! !".into()])]);
+	}
+
+	#[test]
+	fn closest_supported_language() {
+		let supported = [
+			"en",
+			"en-US",
+			"en-GB",
+			"de-DE",
+			"de-AT",
+			"de-DE-x-simple-language",
+		]
+		.map(String::from);
+		let closest = |lang| closest_language(lang, &supported);
+		assert_eq!(closest("en-GB"), Some("en-GB"));
+		assert_eq!(closest("en-gb"), Some("en-GB"));
+		assert_eq!(closest("en-AU"), Some("en"));
+		assert_eq!(closest("EN"), Some("en"));
+		assert_eq!(closest("de"), Some("de-DE"));
+		assert_eq!(closest("de-CH"), Some("de-DE"));
+		assert_eq!(closest("fr"), None);
+	}
 }
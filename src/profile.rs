@@ -0,0 +1,134 @@
+use std::{
+	collections::HashMap,
+	ops::{Not, Range},
+};
+
+use serde::{Deserialize, Serialize};
+use typst::syntax::{
+	ast::{self, AstNode},
+	LinkedNode, Source, SyntaxNode,
+};
+
+/// Conversion settings for documents using a package.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+	/// Packages ("polylux", ...) enabling the profile when imported, always enabled without packages.
+	pub packages: Vec<String>,
+	/// Functions for speaker notes ("pdfpc.speaker-note", ...), their string and content arguments
+	/// are checked separately as they are not part of the layout.
+	pub notes: Vec<String>,
+	/// Functions whose output is not checked.
+	pub ignore: Vec<String>,
+	/// Pages only repeating the content of the next page (overlays of slides) are checked once.
+	pub overlays: bool,
+}
+
+/// Profiles for popular slide packages.
+pub fn built_in() -> HashMap<String, Profile> {
+	let profile = |package: &str, notes: &[&str], ignore: &[&str]| Profile {
+		packages: vec![package.into()],
+		notes: notes.iter().map(|&x| x.into()).collect(),
+		ignore: ignore.iter().map(|&x| x.into()).collect(),
+		overlays: true,
+	};
+	HashMap::from([
+		(
+			"polylux".into(),
+			profile("polylux", &["pdfpc.speaker-note"], &["pdfpc.config"]),
+		),
+		(
+			"touying".into(),
+			profile("touying", &["speaker-note", "pdfpc.speaker-note"], &[]),
+		),
+	])
+}
+
+/// Profiles for the packages imported by the source or by the main source of the document.
+pub fn active<'a>(
+	profiles: &'a HashMap<String, Profile>,
+	source: &Source,
+	main: &Source,
+) -> Vec<&'a Profile> {
+	let mut packages = Vec::new();
+	imports(source.root(), &mut packages);
+	imports(main.root(), &mut packages);
+	profiles
+		.values()
+		.filter(|profile| {
+			profile.packages.is_empty()
+				|| profile
+					.packages
+					.iter()
+					.any(|package| packages.contains(package))
+		})
+		.collect()
+}
+
+/// Source ranges of calls to ignored functions.
+pub fn ignored(source: &Source, profiles: &[&Profile]) -> Vec<Range<usize>> {
+	let names = profiles
+		.iter()
+		.flat_map(|profile| &profile.ignore)
+		.collect::<Vec<_>>();
+	call_ranges(source, &names)
+}
+
+/// Source ranges of calls to speaker note functions.
+pub fn notes(source: &Source, profiles: &[&Profile]) -> Vec<Range<usize>> {
+	let names = profiles
+		.iter()
+		.flat_map(|profile| &profile.notes)
+		.collect::<Vec<_>>();
+	call_ranges(source, &names)
+}
+
+fn call_ranges(source: &Source, names: &[&String]) -> Vec<Range<usize>> {
+	let mut ranges = Vec::new();
+	if names.is_empty().not() {
+		calls(&LinkedNode::new(source.root()), names, &mut ranges);
+	}
+	ranges
+}
+
+/// Calls to the functions, `speaker-note` also matches `pdfpc.speaker-note`.
+pub(crate) fn is_call<'a>(node: &'a SyntaxNode, names: &[&String]) -> Option<ast::FuncCall<'a>> {
+	let call = node.cast::<ast::FuncCall>()?;
+	let callee = call.callee().to_untyped().clone().into_text();
+	names
+		.iter()
+		.any(|name| {
+			callee == name.as_str()
+				|| callee
+					.strip_suffix(name.as_str())
+					.is_some_and(|x| x.ends_with('.'))
+		})
+		.then_some(call)
+}
+
+fn calls(node: &LinkedNode, names: &[&String], ranges: &mut Vec<Range<usize>>) {
+	if is_call(node.get(), names).is_some() {
+		ranges.push(node.range());
+		return;
+	}
+	for child in node.children() {
+		calls(&child, names, ranges);
+	}
+}
+
+/// Names of the imported packages (`@preview/polylux:0.3.1`).
+fn imports(node: &SyntaxNode, packages: &mut Vec<String>) {
+	if let Some(import) = node.cast::<ast::ModuleImport>() {
+		if let ast::Expr::Str(path) = import.source() {
+			let path = path.get();
+			let name = path
+				.strip_prefix('@')
+				.and_then(|x| x.split_once('/'))
+				.and_then(|(_, x)| x.split(':').next());
+			packages.extend(name.map(String::from));
+		}
+	}
+	for child in node.children() {
+		imports(child, packages);
+	}
+}
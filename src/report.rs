@@ -0,0 +1,309 @@
+use std::{
+	fmt::Write,
+	ops::{Not, Range},
+};
+
+use serde::{Deserialize, Serialize};
+use typst::syntax::Source;
+
+use crate::Diagnostic;
+
+/// Characters shown before and after a finding.
+const CONTEXT: usize = 40;
+const MAX_REPLACEMENTS: usize = 5;
+const TITLE: &str = "Language check report";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+	Html,
+	Markdown,
+}
+
+/// Findings of a file.
+pub struct FileReport<'a> {
+	/// Name shown for the file, usually the path relative to the project root.
+	pub name: String,
+	pub source: &'a Source,
+	pub diagnostics: Vec<Diagnostic>,
+}
+
+struct Finding {
+	line: usize,
+	column: usize,
+	message: String,
+	before: String,
+	found: String,
+	after: String,
+	replacements: Vec<String>,
+}
+
+struct Group {
+	rule_id: String,
+	rule_description: String,
+	findings: Vec<Finding>,
+}
+
+/// Standalone document with the findings grouped by file and rule.
+pub fn render(files: &[FileReport], format: Format) -> String {
+	let files = files
+		.iter()
+		.filter(|file| file.diagnostics.is_empty().not())
+		.map(|file| (file.name.as_str(), groups(file)))
+		.collect::<Vec<_>>();
+	let total = files
+		.iter()
+		.flat_map(|(_, groups)| groups)
+		.map(|group| group.findings.len())
+		.sum::<usize>();
+	let summary = format!("{} findings in {} files.", total, files.len());
+	match format {
+		Format::Html => html(&files, &summary),
+		Format::Markdown => markdown(&files, &summary),
+	}
+}
+
+/// Groups in order of their first finding.
+fn groups(file: &FileReport) -> Vec<Group> {
+	let mut diagnostics = file.diagnostics.iter().collect::<Vec<_>>();
+	diagnostics.sort_by_key(|diagnostic| diagnostic.locations[0].start);
+	let mut groups = Vec::<Group>::new();
+	for diagnostic in diagnostics {
+		let finding = finding(file.source, diagnostic);
+		match groups
+			.iter_mut()
+			.find(|group| group.rule_id == diagnostic.rule_id)
+		{
+			Some(group) => group.findings.push(finding),
+			None => groups.push(Group {
+				rule_id: diagnostic.rule_id.clone(),
+				rule_description: diagnostic.rule_description.clone(),
+				findings: vec![finding],
+			}),
+		}
+	}
+	groups
+}
+
+fn finding(source: &Source, diagnostic: &Diagnostic) -> Finding {
+	let text = source.text();
+	let Range { start, end } = diagnostic.locations[0];
+	let line = source.byte_to_line(start).unwrap_or_default();
+	let line_start = text[..start].rfind('\n').map_or(0, |idx| idx + 1);
+	let line_end = text[end..].find('\n').map_or(text.len(), |idx| end + idx);
+
+	let before = text[line_start..start].chars().rev().collect::<Vec<_>>();
+	let mut before = before.iter().take(CONTEXT).rev().collect::<String>();
+	if text[line_start..start].chars().nth(CONTEXT).is_some() {
+		before.insert(0, '…');
+	}
+	let mut after = text[end..line_end]
+		.chars()
+		.take(CONTEXT)
+		.collect::<String>();
+	if text[end..line_end].chars().nth(CONTEXT).is_some() {
+		after.push('…');
+	}
+
+	Finding {
+		line: line + 1,
+		column: text[line_start..start].chars().count() + 1,
+		message: diagnostic.message.clone(),
+		before,
+		found: text[start..end].replace('\n', " "),
+		after,
+		replacements: diagnostic
+			.replacements
+			.iter()
+			.filter(|replacement| replacement.trim().is_empty().not())
+			.take(MAX_REPLACEMENTS)
+			.cloned()
+			.collect(),
+	}
+}
+
+fn markdown(files: &[(&str, Vec<Group>)], summary: &str) -> String {
+	let mut out = format!("# {}\n\n{}\n", TITLE, summary);
+	for (name, groups) in files {
+		write!(out, "\n## {}\n", escape_markdown(name)).unwrap();
+		for group in groups {
+			write!(
+				out,
+				"\n### {} (`{}`, {})\n\n",
+				escape_markdown(&group.rule_description),
+				group.rule_id,
+				group.findings.len(),
+			)
+			.unwrap();
+			for finding in &group.findings {
+				writeln!(
+					out,
+					"- **{}:{}** {}  ",
+					finding.line,
+					finding.column,
+					escape_markdown(&finding.message),
+				)
+				.unwrap();
+				write!(
+					out,
+					"  > {}**{}**{}",
+					escape_markdown(&finding.before),
+					escape_markdown(&finding.found),
+					escape_markdown(&finding.after),
+				)
+				.unwrap();
+				if finding.replacements.is_empty().not() {
+					let replacements = finding
+						.replacements
+						.iter()
+						.map(|replacement| format!("\"{}\"", escape_markdown(replacement)))
+						.collect::<Vec<_>>();
+					write!(out, "  \n  > Suggestions: {}", replacements.join(", ")).unwrap();
+				}
+				out.push('\n');
+			}
+		}
+	}
+	out
+}
+
+fn html(files: &[(&str, Vec<Group>)], summary: &str) -> String {
+	let mut out = format!(
+		"<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n{style}\n</style>\n</head>\n<body>\n<h1>{title}</h1>\n<p>{summary}</p>\n",
+		title = TITLE,
+		style = STYLE,
+		summary = escape_html(summary),
+	);
+	for (name, groups) in files {
+		writeln!(out, "<h2>{}</h2>", escape_html(name)).unwrap();
+		for group in groups {
+			writeln!(
+				out,
+				"<h3>{} <code>{}</code> ({})</h3>\n<ul>",
+				escape_html(&group.rule_description),
+				escape_html(&group.rule_id),
+				group.findings.len(),
+			)
+			.unwrap();
+			for finding in &group.findings {
+				write!(
+					out,
+					"<li><span class=\"position\">{}:{}</span> {}<blockquote>{}<mark>{}</mark>{}</blockquote>",
+					finding.line,
+					finding.column,
+					escape_html(&finding.message),
+					escape_html(&finding.before),
+					escape_html(&finding.found),
+					escape_html(&finding.after),
+				)
+				.unwrap();
+				if finding.replacements.is_empty().not() {
+					let replacements = finding
+						.replacements
+						.iter()
+						.map(|replacement| format!("<code>{}</code>", escape_html(replacement)))
+						.collect::<Vec<_>>();
+					write!(out, "<p>Suggestions: {}</p>", replacements.join(", ")).unwrap();
+				}
+				writeln!(out, "</li>").unwrap();
+			}
+			writeln!(out, "</ul>").unwrap();
+		}
+	}
+	out += "</body>\n</html>\n";
+	out
+}
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 60em; margin: auto; padding: 1em; }
+blockquote { font-family: monospace; background: #f4f4f4; margin: 0.3em 0; padding: 0.3em 0.6em; }
+mark { background: #ffd54f; }
+.position { color: #666; font-family: monospace; }
+li { margin-bottom: 0.8em; }";
+
+fn escape_html(text: &str) -> String {
+	let mut out = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'&' => out += "&amp;",
+			'<' => out += "&lt;",
+			'>' => out += "&gt;",
+			'"' => out += "&quot;",
+			c => out.push(c),
+		}
+	}
+	out
+}
+
+fn escape_markdown(text: &str) -> String {
+	let mut out = String::with_capacity(text.len());
+	for c in text.chars() {
+		if matches!(
+			c,
+			'\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~' | '$'
+		) {
+			out.push('\\');
+		}
+		out.push(c);
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Severity;
+
+	fn rendered(text: &str, found: &str, message: &str, format: Format) -> String {
+		let source = Source::detached(text);
+		let start = text.find(found).unwrap();
+		let range = start..start + found.len();
+		let diagnostic = Diagnostic {
+			locations: vec![range],
+			message: message.into(),
+			replacements: vec!["<b>".into(), " ".into()],
+			fixes: Vec::new(),
+			rule_description: "Rule & description".into(),
+			rule_id: "RULE_ID".into(),
+			category: "TYPOS".into(),
+			severity: Severity::Warning,
+		};
+		let files = [FileReport {
+			name: "dir/<file>.typ".into(),
+			source: &source,
+			diagnostics: vec![diagnostic],
+		}];
+		render(&files, format)
+	}
+
+	#[test]
+	fn html_is_escaped() {
+		let html = rendered("a < b & \"teh\"", "teh", "Use \"the\" <here>", Format::Html);
+		assert!(html.contains("<h2>dir/&lt;file&gt;.typ</h2>"));
+		assert!(html.contains("Rule &amp; description <code>RULE_ID</code> (1)"));
+		assert!(html.contains("Use &quot;the&quot; &lt;here&gt;"));
+		assert!(
+			html.contains("<blockquote>a &lt; b &amp; &quot;<mark>teh</mark>&quot;</blockquote>")
+		);
+		// Empty replacements are left out.
+		assert!(html.contains("<p>Suggestions: <code>&lt;b&gt;</code></p>"));
+		assert!(html.contains("1 findings in 1 files."));
+	}
+
+	#[test]
+	fn markdown_is_escaped() {
+		let markdown = rendered("*a* `teh` $x$", "teh", "Use *the*", Format::Markdown);
+		assert!(markdown.contains("## dir/\\<file\\>.typ"));
+		assert!(markdown.contains("- **1:6** Use \\*the\\*"));
+		assert!(markdown.contains("> \\*a\\* \\`**teh**\\` \\$x\\$"));
+		assert!(markdown.contains("Suggestions: \"\\<b\\>\""));
+	}
+
+	#[test]
+	fn context_is_shortened() {
+		let text = format!("{}teh{}\nnext line", "x".repeat(50), "y".repeat(50));
+		let markdown = rendered(&text, "teh", "Typo", Format::Markdown);
+		let context = format!("> …{}**teh**{}…", "x".repeat(CONTEXT), "y".repeat(CONTEXT));
+		assert!(markdown.contains(&context), "{}", markdown);
+		assert!(markdown.contains("next line").not());
+	}
+}
@@ -0,0 +1,214 @@
+use std::{
+	collections::{HashMap, HashSet},
+	ops::Not,
+};
+
+use super::chars;
+use crate::{convert::Mapping, Severity, Suggestion};
+
+/// Shorter sentences ("See above.") are repeated on purpose.
+const MIN_WORDS: usize = 5;
+const OPENING_WORDS: usize = 3;
+const OPENING_THRESHOLD: usize = 3;
+
+struct Word {
+	start: usize,
+	end: usize,
+	text: String,
+}
+
+struct Sentence {
+	paragraph: usize,
+	start: usize,
+	end: usize,
+	words: Vec<Word>,
+}
+
+impl Sentence {
+	fn key(&self, words: usize) -> String {
+		let mut key = String::new();
+		for word in self.words.iter().take(words) {
+			if key.is_empty().not() {
+				key.push(' ');
+			}
+			key += &word.text;
+		}
+		key
+	}
+}
+
+pub fn check(paragraphs: &[(String, Mapping)], suggestions: &mut [Vec<Suggestion>]) {
+	let mut sentences = Vec::new();
+	for (paragraph, (text, _)) in paragraphs.iter().enumerate() {
+		split(paragraph, text, &mut sentences);
+	}
+
+	// The same source text shown on several pages (overlays, headers, ...) is no repetition.
+	let mut origins = HashSet::new();
+	let shown_before = sentences
+		.iter()
+		.map(|sentence| {
+			paragraphs[sentence.paragraph]
+				.1
+				.origin(sentence.start)
+				.is_some_and(|origin| origins.insert(origin).not())
+		})
+		.collect::<Vec<_>>();
+
+	let mut seen = HashSet::new();
+	let mut repeated = shown_before.clone();
+	for (idx, sentence) in sentences.iter().enumerate() {
+		if shown_before[idx] || sentence.words.len() < MIN_WORDS {
+			continue;
+		}
+		if seen.insert(sentence.key(usize::MAX)) {
+			continue;
+		}
+		repeated[idx] = true;
+		suggestions[sentence.paragraph].push(Suggestion {
+			start: sentence.start,
+			end: sentence.end,
+			message: "This sentence already appears earlier in the document.".into(),
+			replacements: Vec::new(),
+			rule_description: "Repeated sentence".into(),
+			rule_id: "DUPLICATE_SENTENCE".into(),
+			category: "REDUNDANCY".into(),
+			severity: Severity::Hint,
+		});
+	}
+
+	let mut openings = HashMap::<String, Vec<usize>>::new();
+	for (idx, sentence) in sentences.iter().enumerate() {
+		if repeated[idx] || sentence.words.len() <= OPENING_WORDS {
+			continue;
+		}
+		openings
+			.entry(sentence.key(OPENING_WORDS))
+			.or_default()
+			.push(idx);
+	}
+	for (opening, occurrences) in openings {
+		if occurrences.len() < OPENING_THRESHOLD {
+			continue;
+		}
+		for idx in occurrences.iter().copied() {
+			let sentence = &sentences[idx];
+			suggestions[sentence.paragraph].push(Suggestion {
+				start: sentence.words[0].start,
+				end: sentence.words[OPENING_WORDS - 1].end,
+				message: format!(
+					"{} sentences start with \"{}\", consider varying the wording.",
+					occurrences.len(),
+					opening
+				),
+				replacements: Vec::new(),
+				rule_description: "Repeated sentence opening".into(),
+				rule_id: "REPEATED_SENTENCE_OPENING".into(),
+				category: "STYLE".into(),
+				severity: Severity::Hint,
+			});
+		}
+	}
+}
+
+/// Split the text at sentence terminators and paragraph breaks.
+fn split(paragraph: usize, text: &str, sentences: &mut Vec<Sentence>) {
+	let mut words = Vec::new();
+	let mut word: Option<Word> = None;
+	let mut end = 0;
+	let mut previous = ' ';
+
+	let mut finish = |words: &mut Vec<Word>, end: usize| {
+		let words = std::mem::take(words);
+		if words.is_empty() {
+			return;
+		}
+		let start = words[0].start;
+		sentences.push(Sentence { paragraph, start, end, words });
+	};
+
+	for (position, c) in chars(text) {
+		if c.is_alphanumeric() {
+			let word = word.get_or_insert_with(|| Word {
+				start: position,
+				end: position,
+				text: String::new(),
+			});
+			word.end = position + c.len_utf16();
+			word.text.extend(c.to_lowercase());
+		} else if let Some(word) = word.take() {
+			words.push(word);
+		}
+		if c.is_whitespace().not() {
+			end = position + c.len_utf16();
+		}
+		if matches!(c, '.' | '!' | '?') || (c == '\n' && previous == '\n') {
+			finish(&mut words, end);
+		}
+		previous = c;
+	}
+	words.extend(word);
+	finish(&mut words, end);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn sentences(text: &str) -> Vec<String> {
+		let mut sentences = Vec::new();
+		split(0, text, &mut sentences);
+		sentences.iter().map(|x| x.key(usize::MAX)).collect()
+	}
+
+	fn check_text(text: &str) -> Vec<Suggestion> {
+		let paragraphs = [(text.to_string(), Mapping::generated(text))];
+		let mut suggestions = vec![Vec::new()];
+		check(&paragraphs, &mut suggestions);
+		suggestions.remove(0)
+	}
+
+	#[test]
+	fn split_at_terminators_and_paragraphs() {
+		assert_eq!(
+			sentences("One two. Three? Four!\n\nFive six\nseven"),
+			["one two", "three", "four", "five six seven"],
+		);
+	}
+
+	#[test]
+	fn split_positions_in_utf16() {
+		let mut sentences = Vec::new();
+		split(0, "😀 Ab cd. Ef", &mut sentences);
+		assert_eq!(sentences[0].start, 3);
+		assert_eq!(sentences[0].end, 9);
+		assert_eq!(sentences[1].start, 10);
+	}
+
+	#[test]
+	fn repeated_sentence() {
+		let text =
+			"The results are shown in the table. Something else happens here. The results are \
+		            shown in the table.";
+		let suggestions = check_text(text);
+		assert_eq!(suggestions.len(), 1);
+		assert_eq!(suggestions[0].rule_id, "DUPLICATE_SENTENCE");
+		assert_eq!(suggestions[0].start, text.rfind("The").unwrap());
+	}
+
+	#[test]
+	fn short_sentences_are_not_repeated() {
+		assert!(check_text("See above. Other text. See above.").is_empty());
+	}
+
+	#[test]
+	fn repeated_openings() {
+		let text = "We then measure a. We then measure b. We then measure c. We now stop.";
+		let suggestions = check_text(text);
+		assert_eq!(suggestions.len(), 3);
+		assert!(suggestions
+			.iter()
+			.all(|x| x.rule_id == "REPEATED_SENTENCE_OPENING"));
+		assert_eq!((suggestions[0].start, suggestions[0].end), (0, 15));
+	}
+}
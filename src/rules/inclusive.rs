@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use super::{find, positions};
+use crate::{Severity, Suggestion};
+
+const ENGLISH: &[(&str, &[&str])] = &[
+	("whitelist", &["allowlist", "allow list"]),
+	("whitelisted", &["allowlisted", "allowed"]),
+	("blacklist", &["denylist", "blocklist"]),
+	("blacklisted", &["denylisted", "blocked"]),
+	("master branch", &["main branch"]),
+	("master-slave", &["primary-replica", "leader-follower"]),
+	("sanity check", &["confidence check", "coherence check"]),
+	("dummy value", &["placeholder value", "sample value"]),
+	("man-hours", &["person-hours", "work hours"]),
+	("manpower", &["workforce", "staff"]),
+	("mankind", &["humankind", "humanity"]),
+	("man-made", &["artificial", "synthetic"]),
+	("chairman", &["chairperson", "chair"]),
+	("grandfathered", &["legacy", "exempt"]),
+	("he or she", &["they"]),
+	("his or her", &["their"]),
+	("him or her", &["them"]),
+	("handicapped", &["disabled"]),
+];
+
+const GERMAN: &[(&str, &[&str])] = &[
+	("whitelist", &["Positivliste", "Allowlist"]),
+	("blacklist", &["Sperrliste", "Denylist"]),
+	("mannstunden", &["Personenstunden", "Arbeitsstunden"]),
+	("behinderte", &["Menschen mit Behinderung"]),
+];
+
+fn built_in(lang: &str) -> &'static [(&'static str, &'static [&'static str])] {
+	match lang {
+		"en" => ENGLISH,
+		"de" => GERMAN,
+		_ => &[],
+	}
+}
+
+pub fn check(
+	text: &str,
+	lang: &str,
+	terms: &HashMap<String, HashMap<String, Vec<String>>>,
+	suggestions: &mut Vec<Suggestion>,
+) {
+	let mut all = built_in(lang)
+		.iter()
+		.map(|(term, alternatives)| {
+			let alternatives = alternatives.iter().map(|&x| x.into()).collect();
+			(term.to_lowercase(), alternatives)
+		})
+		.collect::<HashMap<String, Vec<String>>>();
+	if let Some(terms) = terms.get(lang) {
+		for (term, alternatives) in terms {
+			all.insert(term.to_lowercase(), alternatives.clone());
+		}
+	}
+
+	let chars = positions(text, true);
+	let original = text.encode_utf16().collect::<Vec<_>>();
+	for (term, alternatives) in all {
+		if alternatives.is_empty() {
+			continue;
+		}
+		let phrase = term.chars().collect::<Vec<_>>();
+		for range in find(&chars, &phrase) {
+			let found = String::from_utf16_lossy(&original[range.clone()]);
+			let capitalized = found.chars().next().is_some_and(char::is_uppercase);
+			let replacements = alternatives
+				.iter()
+				.map(|alternative| match capitalized {
+					true => capitalize(alternative),
+					false => alternative.clone(),
+				})
+				.collect::<Vec<_>>();
+			suggestions.push(Suggestion {
+				start: range.start,
+				end: range.end,
+				message: format!(
+					"\"{}\" may be considered non-inclusive or outdated, consider \"{}\".",
+					found,
+					replacements.join("\", \""),
+				),
+				replacements,
+				rule_description: "Inclusive language".into(),
+				rule_id: "INCLUSIVE_LANGUAGE".into(),
+				category: "STYLE".into(),
+				severity: Severity::Information,
+			});
+		}
+	}
+}
+
+fn capitalize(word: &str) -> String {
+	let mut chars = word.chars();
+	match chars.next() {
+		Some(first) => first.to_uppercase().chain(chars).collect(),
+		None => String::new(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn run(
+		text: &str,
+		lang: &str,
+		terms: &HashMap<String, HashMap<String, Vec<String>>>,
+	) -> Vec<Suggestion> {
+		let mut suggestions = Vec::new();
+		check(text, lang, terms, &mut suggestions);
+		suggestions.sort_by_key(|suggestion| suggestion.start);
+		suggestions
+	}
+
+	#[test]
+	fn built_in_terms_at_word_boundaries() {
+		let suggestions = run(
+			"Add it to the whitelist, not the whitelists.",
+			"en",
+			&HashMap::new(),
+		);
+		assert_eq!(suggestions.len(), 1);
+		assert_eq!((suggestions[0].start, suggestions[0].end), (14, 23));
+		assert_eq!(suggestions[0].replacements, ["allowlist", "allow list"]);
+	}
+
+	#[test]
+	fn phrases_match_any_whitespace_and_keep_capitalization() {
+		let suggestions = run("Master\n  branch only.", "en", &HashMap::new());
+		assert_eq!(suggestions.len(), 1);
+		assert_eq!((suggestions[0].start, suggestions[0].end), (0, 15));
+		assert_eq!(suggestions[0].replacements, ["Main branch"]);
+	}
+
+	#[test]
+	fn custom_terms_extend_and_accept() {
+		let terms = HashMap::from([(
+			"en".to_string(),
+			HashMap::from([
+				("Foo".to_string(), vec!["bar".to_string()]),
+				("whitelist".to_string(), Vec::new()),
+			]),
+		)]);
+		let suggestions = run("A foo on the whitelist.", "en", &terms);
+		assert_eq!(suggestions.len(), 1);
+		assert_eq!(suggestions[0].replacements, ["bar"]);
+	}
+
+	#[test]
+	fn positions_in_utf16() {
+		let suggestions = run("😀 mankind", "en", &HashMap::new());
+		assert_eq!((suggestions[0].start, suggestions[0].end), (3, 10));
+	}
+
+	#[test]
+	fn unknown_language() {
+		assert!(run("whitelist", "fr", &HashMap::new()).is_empty());
+	}
+}
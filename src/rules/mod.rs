@@ -0,0 +1,209 @@
+mod duplicates;
+mod inclusive;
+mod typography;
+
+use std::{
+	collections::HashMap,
+	ops::{Not, Range},
+};
+
+use typst::syntax::Source;
+
+use crate::{convert::Mapping, Suggestion};
+
+/// Local checks evaluated on the converted text alongside LanguageTool.
+#[derive(Debug, Clone, Default)]
+pub struct Rules {
+	/// Flag sentences repeated verbatim and heavily repeated sentence openings.
+	pub duplicates: bool,
+	/// Flag non-inclusive or outdated terminology.
+	pub inclusive: bool,
+	/// Additional terms with alternatives for language codes ("en", ...).
+	/// Terms without alternatives are accepted, even if they are built-in.
+	pub inclusive_terms: HashMap<String, HashMap<String, Vec<String>>>,
+	/// Flag missing non-breaking spaces before units, wrong decimal separators and hyphen ranges.
+	pub typography: bool,
+	/// Phrases for language codes ("en-US", "en", ...), findings inside them are ignored.
+	pub allowed_phrases: HashMap<String, Vec<String>>,
+}
+
+impl Rules {
+	/// Suggestions for every paragraph, positions are relative to the paragraph text.
+	pub fn check(&self, source: &Source, paragraphs: &[(String, Mapping)]) -> Vec<Vec<Suggestion>> {
+		let mut suggestions = vec![Vec::new(); paragraphs.len()];
+		if self.duplicates {
+			duplicates::check(paragraphs, &mut suggestions);
+		}
+		for ((text, mapping), suggestions) in paragraphs.iter().zip(&mut suggestions) {
+			let lang = mapping.short_language();
+			if self.inclusive {
+				inclusive::check(text, lang, &self.inclusive_terms, suggestions);
+			}
+			if self.typography {
+				let mut found = Vec::new();
+				typography::check(text, lang, &mut found);
+				// Strings and raw text are written as they should appear.
+				found.retain(|suggestion| {
+					mapping.is_markup(source, suggestion.start..suggestion.end)
+				});
+				suggestions.extend(found);
+			}
+		}
+		for suggestions in &mut suggestions {
+			suggestions.sort_by_key(|suggestion| suggestion.start);
+		}
+		suggestions
+	}
+
+	/// Remove suggestions fully contained in an allowed phrase.
+	pub fn filter(&self, text: &str, lang: &str, suggestions: &mut Vec<Suggestion>) {
+		let short = lang.split('-').next().unwrap_or(lang);
+		let mut phrases = self
+			.allowed_phrases
+			.get(lang)
+			.into_iter()
+			.chain(self.allowed_phrases.get(short).filter(|_| short != lang))
+			.flatten()
+			.peekable();
+		if phrases.peek().is_none() {
+			return;
+		}
+		let chars = positions(text, false);
+		let allowed = phrases
+			.flat_map(|phrase| find(&chars, &phrase.chars().collect::<Vec<_>>()))
+			.collect::<Vec<_>>();
+		suggestions.retain(|suggestion| {
+			allowed
+				.iter()
+				.any(|range| range.start <= suggestion.start && suggestion.end <= range.end)
+				.not()
+		});
+	}
+}
+
+/// Characters with their position in UTF-16 code units, like the LanguageTool offsets.
+fn chars(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+	text.chars().scan(0, |position, c| {
+		let start = *position;
+		*position += c.len_utf16();
+		Some((start, c))
+	})
+}
+
+/// Characters with their range in UTF-16 code units, optionally lowercase.
+fn positions(text: &str, fold: bool) -> Vec<(Range<usize>, char)> {
+	chars(text)
+		.map(|(start, c)| {
+			let range = start..(start + c.len_utf16());
+			if fold {
+				(range, c.to_lowercase().next().unwrap_or(c))
+			} else {
+				(range, c)
+			}
+		})
+		.collect()
+}
+
+/// Occurrences of the phrase at word boundaries, whitespace matches any amount of whitespace.
+fn find(text: &[(Range<usize>, char)], phrase: &[char]) -> Vec<Range<usize>> {
+	let boundary = |idx: usize| text.get(idx).is_none_or(|(_, c)| c.is_alphanumeric().not());
+	let mut occurrences = Vec::new();
+	for start in 0..text.len() {
+		if start > 0 && boundary(start - 1).not() {
+			continue;
+		}
+		let Some(end) = matches_at(text, start, phrase) else {
+			continue;
+		};
+		if end > start && boundary(end) {
+			occurrences.push(text[start].0.start..text[end - 1].0.end);
+		}
+	}
+	occurrences
+}
+
+fn matches_at(text: &[(Range<usize>, char)], mut idx: usize, phrase: &[char]) -> Option<usize> {
+	let mut k = 0;
+	while k < phrase.len() {
+		let c = text.get(idx)?.1;
+		if phrase[k].is_whitespace() {
+			if c.is_whitespace().not() {
+				return None;
+			}
+			while text.get(idx).is_some_and(|(_, c)| c.is_whitespace()) {
+				idx += 1;
+			}
+			while phrase.get(k).is_some_and(|c| c.is_whitespace()) {
+				k += 1;
+			}
+		} else {
+			if c != phrase[k] {
+				return None;
+			}
+			idx += 1;
+			k += 1;
+		}
+	}
+	Some(idx)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Severity;
+
+	fn suggestion(start: usize, end: usize) -> Suggestion {
+		Suggestion {
+			start,
+			end,
+			message: String::new(),
+			replacements: Vec::new(),
+			rule_description: String::new(),
+			rule_id: String::new(),
+			category: String::new(),
+			severity: Severity::Information,
+		}
+	}
+
+	fn filtered(phrases: &[(&str, &str)], text: &str, lang: &str) -> Vec<(usize, usize)> {
+		let mut allowed_phrases = HashMap::<String, Vec<String>>::new();
+		for (lang, phrase) in phrases {
+			allowed_phrases
+				.entry(lang.to_string())
+				.or_default()
+				.push(phrase.to_string());
+		}
+		let rules = Rules { allowed_phrases, ..Default::default() };
+		let mut suggestions = vec![suggestion(0, 3), suggestion(4, 9), suggestion(8, 14)];
+		rules.filter(text, lang, &mut suggestions);
+		suggestions.iter().map(|x| (x.start, x.end)).collect()
+	}
+
+	#[test]
+	fn contained_suggestions_are_removed() {
+		let text = "Our Typst  Lab is here";
+		assert_eq!(
+			filtered(&[("en", "Typst")], text, "en-US"),
+			[(0, 3), (8, 14)]
+		);
+		assert_eq!(filtered(&[("en", "Typst Lab")], text, "en-US"), [(0, 3)]);
+		// Case sensitive.
+		assert_eq!(filtered(&[("en", "typst lab")], text, "en-US").len(), 3);
+	}
+
+	#[test]
+	fn phrases_of_other_languages_are_ignored() {
+		let text = "Our Typst  Lab is here";
+		assert_eq!(filtered(&[("de", "Typst Lab")], text, "en-US").len(), 3);
+		assert_eq!(filtered(&[("en-US", "Typst Lab")], text, "en-US").len(), 1);
+		assert_eq!(filtered(&[("en-GB", "Typst Lab")], text, "en-US").len(), 3);
+	}
+
+	#[test]
+	fn phrases_match_at_word_boundaries() {
+		assert_eq!(
+			filtered(&[("en", "ypst La")], "Our Typst  Lab is here", "en").len(),
+			3
+		);
+	}
+}
@@ -0,0 +1,288 @@
+use std::ops::{Not, Range};
+
+use super::positions;
+use crate::{Severity, Suggestion};
+
+const UNITS: &[&str] = &[
+	"nm", "µm", "mm", "cm", "m", "km", "mg", "g", "kg", "s", "ms", "µs", "ns", "min", "h", "Hz",
+	"kHz", "MHz", "GHz", "V", "mV", "kV", "mA", "W", "kW", "MW", "GW", "Wh", "kWh", "J", "kJ",
+	"MJ", "N", "kN", "Pa", "kPa", "MPa", "GPa", "bar", "K", "°C", "°F", "L", "ml", "mL", "mol",
+	"dB", "B", "kB", "KB", "MB", "GB", "TB", "KiB", "MiB", "GiB", "TiB", "px",
+];
+
+/// Words before the numbers of sections, figures, ... ("Section 3.2", "Abb. 1.4"), which are
+/// no decimal numbers.
+const NUMBERING_WORDS: &[&str] = &[
+	"section",
+	"sections",
+	"sec",
+	"chapter",
+	"chapters",
+	"figure",
+	"figures",
+	"fig",
+	"table",
+	"tables",
+	"tab",
+	"equation",
+	"eq",
+	"listing",
+	"appendix",
+	"abschnitt",
+	"abschnitte",
+	"abschnitten",
+	"kapitel",
+	"abbildung",
+	"abbildungen",
+	"abb",
+	"tabelle",
+	"tabellen",
+	"gleichung",
+	"gl",
+	"anhang",
+];
+
+/// Units which are usually part of another word when written without space ("1990s", "4K").
+const AMBIGUOUS_WITHOUT_SPACE: &[&str] = &["s", "K"];
+
+fn decimal_separator(lang: &str) -> Option<char> {
+	match lang {
+		"en" | "zh" | "ja" | "ko" | "he" | "th" => Some('.'),
+		"de" | "fr" | "es" | "it" | "nl" | "pt" | "ru" | "pl" | "sv" | "da" | "uk" | "sl"
+		| "ro" | "ca" | "el" | "cs" | "fi" | "nb" | "nn" | "tr" | "hu" | "sk" | "hr" | "bg" => Some(','),
+		_ => None,
+	}
+}
+
+struct Number {
+	end: usize,
+	separators: Vec<usize>,
+	last_group: usize,
+}
+
+pub fn check(text: &str, lang: &str, suggestions: &mut Vec<Suggestion>) {
+	let chars = positions(text, false);
+	let at = |idx: usize| chars.get(idx).map(|(_, c)| *c);
+
+	let mut idx = 0;
+	while idx < chars.len() {
+		let start = idx;
+		let Some(number) = parse_number(&chars, start) else {
+			idx += 1;
+			continue;
+		};
+		idx = number.end;
+
+		let numbering = previous_word(&chars, start)
+			.is_some_and(|word| NUMBERING_WORDS.contains(&word.to_lowercase().as_str()));
+		if let Some(expected) = decimal_separator(lang).filter(|_| numbering.not()) {
+			if let [separator] = number.separators[..] {
+				let found = chars[separator].1;
+				if found != expected && number.last_group != 3 {
+					suggestions.push(suggestion(
+						chars[separator].0.clone(),
+						format!(
+							"Use \"{}\" as the decimal separator for this language.",
+							expected
+						),
+						expected.to_string(),
+						"Decimal separator",
+						"DECIMAL_SEPARATOR",
+					));
+				}
+			}
+		}
+
+		let range_start = start == 0 || at(start - 1) != Some('-');
+		if range_start && at(number.end) == Some('-') {
+			if let Some(second) = parse_number(&chars, number.end + 1) {
+				if at(second.end) != Some('-') {
+					suggestions.push(suggestion(
+						chars[number.end].0.clone(),
+						"Use an en dash for ranges.".into(),
+						"--".into(),
+						"Range with hyphen",
+						"NUMBER_RANGE_DASH",
+					));
+				}
+			}
+		}
+
+		let (space, unit_start) = match at(number.end) {
+			Some(' ') => (true, number.end + 1),
+			_ => (false, number.end),
+		};
+		let Some(unit) = parse_unit(&chars, unit_start) else {
+			continue;
+		};
+		let unit_text = chars[unit.clone()]
+			.iter()
+			.map(|(_, c)| c)
+			.collect::<String>();
+		if UNITS.contains(&unit_text.as_str()).not() {
+			continue;
+		}
+		let unit_range = chars[unit.start].0.start..chars[unit.end - 1].0.end;
+		if space {
+			suggestions.push(suggestion(
+				chars[number.end].0.clone(),
+				"Use a non-breaking space between number and unit.".into(),
+				"~".into(),
+				"Number and unit",
+				"NUMBER_UNIT_SPACE",
+			));
+		} else if AMBIGUOUS_WITHOUT_SPACE.contains(&unit_text.as_str()).not() {
+			suggestions.push(suggestion(
+				unit_range,
+				"Use a non-breaking space between number and unit.".into(),
+				format!("~{}", unit_text),
+				"Number and unit",
+				"NUMBER_UNIT_SPACE",
+			));
+		}
+	}
+}
+
+/// Digits with single separators in between, not preceded by a word character.
+fn parse_number(chars: &[(Range<usize>, char)], start: usize) -> Option<Number> {
+	if chars.get(start)?.1.is_ascii_digit().not() {
+		return None;
+	}
+	if start > 0 && matches!(chars[start - 1].1, c if c.is_alphanumeric() || c == '.' || c == ',') {
+		return None;
+	}
+	let mut number = Number {
+		end: start,
+		separators: Vec::new(),
+		last_group: 0,
+	};
+	while let Some(&(_, c)) = chars.get(number.end) {
+		match c {
+			c if c.is_ascii_digit() => number.last_group += 1,
+			'.' | ','
+				if chars
+					.get(number.end + 1)
+					.is_some_and(|(_, c)| c.is_ascii_digit()) =>
+			{
+				number.separators.push(number.end);
+				number.last_group = 0;
+			},
+			_ => break,
+		}
+		number.end += 1;
+	}
+	Some(number)
+}
+
+/// Word before the position, separated by whitespace and an optional abbreviation dot.
+fn previous_word(chars: &[(Range<usize>, char)], start: usize) -> Option<String> {
+	let mut end = start;
+	while end > 0 && chars[end - 1].1.is_whitespace() {
+		end -= 1;
+	}
+	if end == start {
+		return None;
+	}
+	if end > 0 && chars[end - 1].1 == '.' {
+		end -= 1;
+	}
+	let mut word_start = end;
+	while word_start > 0 && chars[word_start - 1].1.is_alphabetic() {
+		word_start -= 1;
+	}
+	(word_start < end).then(|| chars[word_start..end].iter().map(|(_, c)| c).collect())
+}
+
+/// Unit candidate, must end at a word boundary.
+fn parse_unit(chars: &[(Range<usize>, char)], start: usize) -> Option<Range<usize>> {
+	let is_unit = |c: char| c.is_alphabetic() || matches!(c, 'µ' | '°');
+	let mut end = start;
+	while chars.get(end).is_some_and(|(_, c)| is_unit(*c)) {
+		end += 1;
+	}
+	if end == start || chars.get(end).is_some_and(|(_, c)| c.is_alphanumeric()) {
+		return None;
+	}
+	Some(start..end)
+}
+
+fn suggestion(
+	range: Range<usize>,
+	message: String,
+	replacement: String,
+	rule_description: &str,
+	rule_id: &str,
+) -> Suggestion {
+	Suggestion {
+		start: range.start,
+		end: range.end,
+		message,
+		replacements: vec![replacement],
+		rule_description: rule_description.into(),
+		rule_id: rule_id.into(),
+		category: "TYPOGRAPHY".into(),
+		severity: Severity::Information,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn rules(text: &str, lang: &str) -> Vec<(String, String)> {
+		let mut suggestions = Vec::new();
+		check(text, lang, &mut suggestions);
+		suggestions
+			.into_iter()
+			.map(|x| (x.rule_id, x.replacements[0].clone()))
+			.collect()
+	}
+
+	#[test]
+	fn decimal_separator() {
+		assert_eq!(
+			rules("Es sind 3.5 Liter.", "de"),
+			[("DECIMAL_SEPARATOR".into(), ",".into())],
+		);
+		assert!(rules("It is 3.5 liters.", "en").is_empty());
+		// Thousands separator.
+		assert!(rules("Es sind 3.500 Liter.", "de").is_empty());
+		assert!(rules("Version 1.2.3 ist neu.", "de").is_empty());
+	}
+
+	#[test]
+	fn numbering_is_no_decimal() {
+		assert!(rules("Siehe Abschnitt 3.2 und Abb. 1.4 oder Kapitel 2.1.", "de").is_empty());
+		assert!(rules("See Figure 1,4.", "en").is_empty());
+		assert_eq!(rules("Mit 3.2 Metern.", "de").len(), 1);
+	}
+
+	#[test]
+	fn ranges() {
+		assert_eq!(
+			rules("Pages 10-12.", "en"),
+			[("NUMBER_RANGE_DASH".into(), "--".into())],
+		);
+		assert!(rules("Call 555-123-456.", "en").is_empty());
+	}
+
+	#[test]
+	fn units() {
+		assert_eq!(
+			rules("It weighs 5 kg and 5kg.", "en"),
+			[
+				("NUMBER_UNIT_SPACE".into(), "~".into()),
+				("NUMBER_UNIT_SPACE".into(), "~kg".into()),
+			],
+		);
+		assert!(rules("In the 1990s at 4K.", "en").is_empty());
+		assert!(rules("It has 5 kgx.", "en").is_empty());
+	}
+
+	#[test]
+	fn previous_words() {
+		let chars = positions("Abb. 1", false);
+		assert_eq!(previous_word(&chars, 5).as_deref(), Some("Abb"));
+		assert_eq!(previous_word(&chars, 0), None);
+	}
+}
@@ -0,0 +1,145 @@
+use std::ops::Range;
+
+use typst::syntax::{
+	ast::{self, AstNode},
+	LinkedNode, Source, SyntaxKind, SyntaxNode,
+};
+
+struct Heading {
+	depth: usize,
+	path: Vec<String>,
+	range: Range<usize>,
+}
+
+/// Byte ranges of the source selected by the scope.
+///
+/// Entries are labels (`<results>`) or heading paths (`Methods/Setup`, `= Results`).
+/// A label selects the labelled element, or the whole section for labelled headings.
+/// A heading path selects the section up to the next heading of the same or higher level.
+pub fn ranges(source: &Source, scope: &[String]) -> Vec<Range<usize>> {
+	let root = LinkedNode::new(source.root());
+	let headings = headings(&root);
+	let mut ranges = Vec::new();
+	for entry in scope {
+		let entry = entry.trim();
+		if let Some(label) = entry.strip_prefix('<').and_then(|x| x.strip_suffix('>')) {
+			labelled(&root, label, &headings, &mut ranges);
+		} else {
+			let path = entry
+				.trim_start_matches('=')
+				.split('/')
+				.map(normalize)
+				.collect::<Vec<_>>();
+			ranges.extend(
+				headings
+					.iter()
+					.filter(|heading| heading.path.ends_with(&path))
+					.map(|heading| heading.range.clone()),
+			);
+		}
+	}
+	ranges
+}
+
+/// Headings of the top level markup with their section range.
+fn headings(root: &LinkedNode) -> Vec<Heading> {
+	let mut headings = Vec::<Heading>::new();
+	for node in root.children() {
+		let Some(heading) = node.cast::<ast::Heading>() else {
+			continue;
+		};
+		let depth = heading.depth().get();
+		let mut path = headings
+			.iter()
+			.rev()
+			.find(|parent| parent.depth < depth)
+			.map(|parent| parent.path.clone())
+			.unwrap_or_default();
+		path.push(normalize(&title(heading.body().to_untyped())));
+		for previous in headings.iter_mut().rev() {
+			if previous.range.end == root.range().end && previous.depth >= depth {
+				previous.range.end = node.range().start;
+			}
+		}
+		headings.push(Heading {
+			depth,
+			path,
+			range: node.range().start..root.range().end,
+		});
+	}
+	headings
+}
+
+fn labelled(node: &LinkedNode, label: &str, headings: &[Heading], ranges: &mut Vec<Range<usize>>) {
+	if node.kind() == SyntaxKind::Label && node.text().trim_matches(['<', '>']) == label {
+		let element = std::iter::successors(node.parent().cloned(), |node| node.parent().cloned())
+			.find(|node| node.kind() == SyntaxKind::Heading)
+			.or_else(|| node.prev_sibling());
+		if let Some(element) = element {
+			let section = headings
+				.iter()
+				.find(|section| section.range.start == element.offset());
+			ranges.push(section.map_or(element.range(), |section| section.range.clone()));
+		}
+	}
+	for child in node.children() {
+		labelled(&child, label, headings, ranges);
+	}
+}
+
+fn title(node: &SyntaxNode) -> String {
+	match node.kind() {
+		SyntaxKind::Text => node.text().to_string(),
+		SyntaxKind::Space => " ".into(),
+		SyntaxKind::Label => String::new(),
+		_ => node.children().map(title).collect(),
+	}
+}
+
+fn normalize(text: &str) -> String {
+	text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TEXT: &str =
+		"Intro\n= Methods\nA\n== Setup\nB\n== Data <data>\nC\n= Results <results>\nD $x$ <eq>\n";
+
+	fn selected(scope: &[&str]) -> Vec<&'static str> {
+		let source = Source::detached(TEXT);
+		let scope = scope.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+		ranges(&source, &scope)
+			.into_iter()
+			.map(|range| TEXT[range].trim_end())
+			.collect()
+	}
+
+	#[test]
+	fn heading_paths() {
+		assert_eq!(
+			selected(&["Methods"]),
+			["= Methods\nA\n== Setup\nB\n== Data <data>\nC"]
+		);
+		assert_eq!(selected(&["Methods/Setup"]), ["== Setup\nB"]);
+		assert_eq!(selected(&["= Setup"]), ["== Setup\nB"]);
+		assert_eq!(selected(&["Results/Setup"]), Vec::<&str>::new());
+	}
+
+	#[test]
+	fn labels() {
+		assert_eq!(selected(&["<data>"]), ["== Data <data>\nC"]);
+		assert_eq!(
+			selected(&["<results>"]),
+			["= Results <results>\nD $x$ <eq>"]
+		);
+		assert_eq!(selected(&["<eq>"]), ["$x$"]);
+		assert_eq!(selected(&["<missing>"]), Vec::<&str>::new());
+	}
+
+	#[test]
+	fn several_entries() {
+		assert_eq!(selected(&["Setup", " <eq> "]), ["== Setup\nB", "$x$"]);
+	}
+}
@@ -0,0 +1,105 @@
+use std::{collections::HashMap, ops::Not, path::Path};
+
+use spellbook::Dictionary;
+
+use crate::{Severity, Suggestion};
+
+const MAX_REPLACEMENTS: usize = 5;
+
+/// Quick spellcheck with hunspell dictionaries, used before the LanguageTool results are available.
+#[derive(Default)]
+pub struct Spellcheck {
+	dictionaries: HashMap<String, Dictionary>,
+}
+
+impl Spellcheck {
+	/// Load the dictionary for a language code ("en-US", "en", ...).
+	/// The `.aff` file has to be next to the `.dic` file.
+	pub fn load(&mut self, lang: String, dic: &Path) -> anyhow::Result<()> {
+		let aff = std::fs::read_to_string(dic.with_extension("aff"))?;
+		let dic = std::fs::read_to_string(dic)?;
+		let dictionary = Dictionary::new(&aff, &dic).map_err(|err| anyhow::anyhow!("{}", err))?;
+		self.dictionaries.insert(lang, dictionary);
+		Ok(())
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.dictionaries.is_empty()
+	}
+
+	/// Add words to the dictionaries of the language code, all dictionaries for short codes ("en").
+	pub fn allow_words(&mut self, lang: &str, words: &[String]) {
+		for (code, dictionary) in &mut self.dictionaries {
+			if code != lang && code.split('-').next() != Some(lang) {
+				continue;
+			}
+			for word in words {
+				if let Err(err) = dictionary.add(word) {
+					eprintln!("Failed to add \"{}\": {}", word, err);
+				}
+			}
+		}
+	}
+
+	/// Misspelled words, without a dictionary for the language nothing is found.
+	pub fn check(&self, text: &str, lang: &str) -> Vec<Suggestion> {
+		let short = lang.split('-').next().unwrap_or(lang);
+		let Some(dictionary) = self
+			.dictionaries
+			.get(lang)
+			.or_else(|| self.dictionaries.get(short))
+		else {
+			return Vec::new();
+		};
+
+		let mut suggestions = Vec::new();
+		for (start, word) in words(text) {
+			if dictionary.check(word) {
+				continue;
+			}
+			let mut replacements = Vec::new();
+			dictionary.suggest(word, &mut replacements);
+			replacements.truncate(MAX_REPLACEMENTS);
+			suggestions.push(Suggestion {
+				start,
+				end: start + word.encode_utf16().count(),
+				message: format!("\"{}\" is not in the dictionary.", word),
+				replacements,
+				rule_description: "Possible spelling mistake".into(),
+				rule_id: "LOCAL_SPELLING".into(),
+				category: "TYPOS".into(),
+				severity: Severity::Information,
+			});
+		}
+		suggestions
+	}
+}
+
+/// Words with their position in UTF-16 code units, words next to digits ("3rd") are left out.
+fn words(text: &str) -> Vec<(usize, &str)> {
+	let is_word = |c: char| c.is_alphabetic() || matches!(c, '\'' | '’');
+	let mut words = Vec::new();
+	let mut position = 0;
+	let mut start = None;
+	let mut previous = ' ';
+	for (idx, c) in text.char_indices().chain([(text.len(), ' ')]) {
+		if is_word(c) {
+			start.get_or_insert((idx, position, previous));
+		} else if let Some((begin, begin_position, before)) = start.take() {
+			let word = text[begin..idx].trim_matches(['\'', '’']);
+			let offset = text[begin..idx].find(word).unwrap_or(0);
+			let digits = before.is_ascii_digit() || c.is_ascii_digit();
+			if word.is_empty().not() && digits.not() {
+				words.push((
+					begin_position + text[begin..begin + offset].encode_utf16().count(),
+					word,
+				));
+			}
+		}
+		if start.is_none() {
+			previous = c;
+		}
+		position += c.len_utf16();
+	}
+	words
+}